@@ -0,0 +1,199 @@
+//! Names, categories and icons of the ids in [`IdentifierType`].
+//!
+//! The bundled table covers the ids known when it was written. Tools can load
+//! their own table with [`IdentifierDatabase::from_ron`], merge it over the
+//! bundled one and install it with [`set_identifier_database`].
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use serde::Deserialize;
+
+use crate::parser::IdentifierType;
+
+const BUNDLED: &str = include_str!("identifiers.ron");
+
+static DATABASE: RwLock<Option<Arc<IdentifierDatabase>>> = RwLock::new(None);
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IdentifierInfo {
+    pub name: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Name of the icon, for viewers that ship an icon set.
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+/// Lookup tables from the numeric ids of each [`IdentifierType`] variant.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IdentifierDatabase {
+    #[serde(default)]
+    pub items: HashMap<u16, IdentifierInfo>,
+    #[serde(default)]
+    pub enemies: HashMap<u16, IdentifierInfo>,
+    /// By gear alias, used for both [`IdentifierType::Gear`] and [`IdentifierType::AliasGear`].
+    #[serde(default)]
+    pub gear: HashMap<u16, IdentifierInfo>,
+    #[serde(default)]
+    pub vanity: HashMap<u16, IdentifierInfo>,
+}
+
+impl IdentifierDatabase {
+    /// The table shipped with this crate.
+    pub fn bundled() -> Self {
+        Self::from_ron(BUNDLED).expect("bundled identifier database is valid")
+    }
+
+    pub fn from_ron(s: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(s)
+    }
+
+    /// Adds the entries of `other`, replacing the ones with the same id.
+    pub fn merge(&mut self, other: Self) {
+        self.items.extend(other.items);
+        self.enemies.extend(other.enemies);
+        self.gear.extend(other.gear);
+        self.vanity.extend(other.vanity);
+    }
+
+    pub fn get(&self, id: &IdentifierType<'_>) -> Option<&IdentifierInfo> {
+        match *id {
+            IdentifierType::Unknown => None,
+            IdentifierType::Gear(_, alias) | IdentifierType::AliasGear(alias) => {
+                self.gear.get(&alias)
+            }
+            IdentifierType::Item(id) => self.items.get(&id),
+            IdentifierType::Enemy(id) => self.enemies.get(&id),
+            IdentifierType::Vanity(id) => self.vanity.get(&id),
+        }
+    }
+
+    /// Name of `id`, or its kind and number when the table doesn't know it.
+    pub fn name(&self, id: &IdentifierType<'_>) -> String {
+        if let Some(info) = self.get(id) {
+            return info.name.clone();
+        }
+        match *id {
+            IdentifierType::Unknown => "Unknown".to_string(),
+            IdentifierType::Gear(ref gear, alias) => match gear_name(gear) {
+                Some(name) => name.to_string(),
+                None => format!("Gear {alias}"),
+            },
+            IdentifierType::AliasGear(alias) => format!("Gear {alias}"),
+            IdentifierType::Item(id) => format!("Item {id}"),
+            IdentifierType::Enemy(id) => format!("Enemy {id}"),
+            IdentifierType::Vanity(id) => format!("Vanity {id}"),
+        }
+    }
+}
+
+/// Name in the gear string of [`IdentifierType::Gear`]: the `Name` field when
+/// it is the game's serialized gear, otherwise the string itself.
+fn gear_name(gear: &str) -> Option<&str> {
+    let gear = gear.trim();
+    if !gear.starts_with('{') {
+        return (!gear.is_empty()).then_some(gear);
+    }
+    let (_, rest) = gear.split_once("\"Name\"")?;
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let (name, _) = rest.strip_prefix('"')?.split_once('"')?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// The database used by [`IdentifierType::name`] and its `Display` impl,
+/// the bundled one unless replaced.
+pub fn identifier_database() -> Arc<IdentifierDatabase> {
+    if let Some(database) = DATABASE.read().unwrap().as_ref() {
+        return database.clone();
+    }
+    DATABASE
+        .write()
+        .unwrap()
+        .get_or_insert_with(|| Arc::new(IdentifierDatabase::bundled()))
+        .clone()
+}
+
+pub fn set_identifier_database(database: IdentifierDatabase) {
+    *DATABASE.write().unwrap() = Some(Arc::new(database));
+}
+
+impl IdentifierType<'_> {
+    pub fn info(&self) -> Option<IdentifierInfo> {
+        identifier_database().get(self).cloned()
+    }
+
+    pub fn name(&self) -> String {
+        identifier_database().name(self)
+    }
+}
+
+impl fmt::Display for IdentifierType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_ids_and_merges_overrides() {
+        let mut database = IdentifierDatabase::bundled();
+        assert_eq!(database.name(&IdentifierType::Item(102)), "MediPack");
+        assert_eq!(
+            database
+                .get(&IdentifierType::Item(102))
+                .unwrap()
+                .category
+                .as_deref(),
+            Some("Resource")
+        );
+        assert_eq!(database.name(&IdentifierType::Vanity(700)), "Vanity 700");
+        assert_eq!(database.name(&IdentifierType::Unknown), "Unknown");
+
+        let update = IdentifierDatabase::from_ron(
+            r#"(
+                items: { 102: (name: "Med Kit") },
+                gear: { 5: (name: "Shelling S49", category: Some("Pistol")) },
+            )"#,
+        )
+        .unwrap();
+        database.merge(update);
+        assert_eq!(database.name(&IdentifierType::Item(102)), "Med Kit");
+        assert_eq!(database.name(&IdentifierType::Item(101)), "Ammo Pack");
+        assert_eq!(
+            database.name(&IdentifierType::Gear("{}".into(), 5)),
+            "Shelling S49"
+        );
+        assert_eq!(database.name(&IdentifierType::AliasGear(5)), "Shelling S49");
+    }
+
+    #[test]
+    fn names_gear_and_vanity_from_the_bundled_database() {
+        let database = IdentifierDatabase::bundled();
+        assert_eq!(
+            database.name(&IdentifierType::Gear("Shelling S49".into(), 3)),
+            "Shelling S49"
+        );
+        assert_eq!(
+            database.name(&IdentifierType::Gear(
+                r#"{"Ver":1,"Name": "Hanaway PSB","Packet":{}}"#.into(),
+                4
+            )),
+            "Hanaway PSB"
+        );
+        assert_eq!(database.name(&IdentifierType::Gear("".into(), 4)), "Gear 4");
+        assert_eq!(database.name(&IdentifierType::Vanity(1)), "Default Helmet");
+    }
+
+    #[test]
+    fn displays_through_the_installed_database() {
+        assert_eq!(IdentifierType::Item(101).to_string(), "Ammo Pack");
+        assert_eq!(IdentifierType::Enemy(1000).to_string(), "Enemy 1000");
+    }
+}
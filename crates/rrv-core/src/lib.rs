@@ -1,8 +1,12 @@
 #![feature(f16)]
 
+mod identifiers;
+mod navmesh;
 mod parser;
 
 pub mod prelude {
+    pub use crate::identifiers::*;
+    pub use crate::navmesh::*;
     pub use crate::parser::*;
 }
 
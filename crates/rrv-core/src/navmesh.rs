@@ -0,0 +1,480 @@
+//! Spatial index over the triangles of the level geometry.
+
+use glam::Vec3;
+
+use crate::parser::Geometry;
+
+/// Triangles in a leaf of the [`NavMesh`] tree.
+const LEAF_SIZE: usize = 4;
+
+/// Rays this close outside a triangle still hit it, so rays along shared edges
+/// and vertices don't slip through the level.
+const EDGE_TOLERANCE: f32 = 1e-5;
+
+/// Bounding volume hierarchy over the triangles of every [`Geometry`] in a
+/// header, for raycasts against the level and nearest-surface queries.
+///
+/// Queries take an optional dimension; with `None` they search every dimension.
+#[derive(Debug, Default)]
+pub struct NavMesh {
+    triangles: Vec<Triangle>,
+    dimensions: Vec<u8>,
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Triangle {
+    vertices: [Vec3; 3],
+    /// Index of the [`Geometry`] the triangle is from.
+    geometry: usize,
+}
+
+impl Triangle {
+    fn min(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        a.min(b).min(c)
+    }
+
+    fn max(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        a.max(b).max(c)
+    }
+
+    fn centroid(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        (a + b + c) / 3.
+    }
+
+    fn normal(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        (b - a).cross(c - a).normalize_or_zero()
+    }
+
+    /// Distance along the ray to the triangle, Möller–Trumbore.
+    fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let [a, b, c] = self.vertices;
+        let ab = b - a;
+        let ac = c - a;
+        let p = direction.cross(ac);
+        let det = ab.dot(p);
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let inv_det = det.recip();
+        let t = origin - a;
+        let u = t.dot(p) * inv_det;
+        if !(-EDGE_TOLERANCE..=1. + EDGE_TOLERANCE).contains(&u) {
+            return None;
+        }
+        let q = t.cross(ab);
+        let v = direction.dot(q) * inv_det;
+        if v < -EDGE_TOLERANCE || u + v > 1. + EDGE_TOLERANCE {
+            return None;
+        }
+        let distance = ac.dot(q) * inv_det;
+        (distance >= 0.).then_some(distance)
+    }
+
+    /// Point of the triangle closest to `point`, from Real-Time Collision
+    /// Detection 5.1.5.
+    fn closest_point(&self, point: Vec3) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        let ab = b - a;
+        let ac = c - a;
+        let ap = point - a;
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= 0. && d2 <= 0. {
+            return a;
+        }
+
+        let bp = point - b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= 0. && d4 <= d3 {
+            return b;
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0. && d1 >= 0. && d3 <= 0. {
+            return a + ab * (d1 / (d1 - d3));
+        }
+
+        let cp = point - c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= 0. && d5 <= d6 {
+            return c;
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0. && d2 >= 0. && d6 <= 0. {
+            return a + ac * (d2 / (d2 - d6));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+
+        let denom = (va + vb + vc).recip();
+        a + ab * (vb * denom) + ac * (vc * denom)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NodeKind {
+    /// Indices of the two children in [`NavMesh::nodes`].
+    Branch(usize, usize),
+    /// Range of [`NavMesh::triangles`].
+    Leaf(usize, usize),
+}
+
+impl Node {
+    /// Distance along the ray to the box, slab test.
+    fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let mut near = 0f32;
+        let mut far = f32::MAX;
+        for axis in 0..3 {
+            // A ray parallel to the slab, possibly along one of its faces.
+            if direction[axis] == 0. {
+                if origin[axis] < self.min[axis] || origin[axis] > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (self.min[axis] - origin[axis]) / direction[axis];
+            let t2 = (self.max[axis] - origin[axis]) / direction[axis];
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+        (near <= far).then_some(near)
+    }
+
+    fn distance_squared(&self, point: Vec3) -> f32 {
+        point.distance_squared(point.clamp(self.min, self.max))
+    }
+}
+
+/// Where a ray hit the level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub distance: f32,
+    pub dimension: u8,
+    /// Index of the hit [`Geometry`] in [`Header::level_geometry`](crate::prelude::Header::level_geometry).
+    pub geometry: usize,
+}
+
+/// The point of the level closest to a query point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceHit {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub distance: f32,
+    pub dimension: u8,
+    /// Index of the [`Geometry`] the point is on in [`Header::level_geometry`](crate::prelude::Header::level_geometry).
+    pub geometry: usize,
+}
+
+impl NavMesh {
+    /// Builds the index, skipping faces with indices outside their vertices.
+    pub fn new(level_geometry: &[Geometry]) -> Self {
+        let mut triangles = vec![];
+        for (geometry_idx, geometry) in level_geometry.iter().enumerate() {
+            for face in geometry.indices.chunks_exact(3) {
+                let vertex = |i: u32| {
+                    usize::try_from(i)
+                        .ok()
+                        .and_then(|i| geometry.vertices.get(i))
+                        .copied()
+                };
+                let (Some(a), Some(b), Some(c)) =
+                    (vertex(face[0]), vertex(face[1]), vertex(face[2]))
+                else {
+                    continue;
+                };
+                triangles.push(Triangle {
+                    vertices: [a, b, c],
+                    geometry: geometry_idx,
+                });
+            }
+        }
+
+        let mut navmesh = Self {
+            dimensions: level_geometry.iter().map(|g| g.dimension).collect(),
+            nodes: vec![],
+            triangles: vec![],
+        };
+        if !triangles.is_empty() {
+            let len = triangles.len();
+            navmesh.build(&mut triangles, 0, len);
+        }
+        navmesh.triangles = triangles;
+        navmesh
+    }
+
+    /// Adds the subtree over `triangles[start..end]` and returns its index.
+    fn build(&mut self, triangles: &mut [Triangle], start: usize, end: usize) -> usize {
+        let slice = &mut triangles[start..end];
+        let (min, max) = slice.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), triangle| (min.min(triangle.min()), max.max(triangle.max())),
+        );
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            min,
+            max,
+            kind: NodeKind::Leaf(start, end),
+        });
+        if slice.len() <= LEAF_SIZE {
+            return idx;
+        }
+
+        // Split at the median centroid along the longest axis.
+        let (centroid_min, centroid_max) = slice.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), triangle| (min.min(triangle.centroid()), max.max(triangle.centroid())),
+        );
+        let extent = centroid_max - centroid_min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let mid = slice.len() / 2;
+        slice.select_nth_unstable_by(mid, |a, b| {
+            a.centroid()[axis].total_cmp(&b.centroid()[axis])
+        });
+
+        let left = self.build(triangles, start, start + mid);
+        let right = self.build(triangles, start + mid, end);
+        self.nodes[idx].kind = NodeKind::Branch(left, right);
+        idx
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Smallest box around the whole level, or `None` without any triangles.
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        self.nodes.first().map(|node| (node.min, node.max))
+    }
+
+    fn in_dimension(&self, triangle: &Triangle, dimension: Option<u8>) -> bool {
+        dimension.is_none_or(|dimension| self.dimensions[triangle.geometry] == dimension)
+    }
+
+    /// First surface hit by a ray within `max_distance`.
+    pub fn raycast(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+        dimension: Option<u8>,
+    ) -> Option<RayHit> {
+        let direction = direction.try_normalize()?;
+        let mut best: Option<(f32, &Triangle)> = None;
+        let mut stack = vec![];
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            let limit = best.map_or(max_distance, |(distance, _)| distance);
+            match node.raycast(origin, direction) {
+                Some(distance) if distance <= limit => {}
+                _ => continue,
+            }
+            match node.kind {
+                NodeKind::Branch(left, right) => stack.extend([left, right]),
+                NodeKind::Leaf(start, end) => {
+                    for triangle in &self.triangles[start..end] {
+                        if !self.in_dimension(triangle, dimension) {
+                            continue;
+                        }
+                        let Some(distance) = triangle.raycast(origin, direction) else {
+                            continue;
+                        };
+                        if distance <= best.map_or(max_distance, |(best, _)| best) {
+                            best = Some((distance, triangle));
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|(distance, triangle)| RayHit {
+            point: origin + direction * distance,
+            normal: triangle.normal(),
+            distance,
+            dimension: self.dimensions[triangle.geometry],
+            geometry: triangle.geometry,
+        })
+    }
+
+    /// Closest point of the level to `point`.
+    pub fn nearest(&self, point: Vec3, dimension: Option<u8>) -> Option<SurfaceHit> {
+        let mut best: Option<(f32, Vec3, &Triangle)> = None;
+        let mut stack = vec![];
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            if best.is_some_and(|(best, _, _)| node.distance_squared(point) > best) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Branch(left, right) => {
+                    // Visit the nearer child first, so it can prune the other.
+                    let (near, far) = if self.nodes[left].distance_squared(point)
+                        <= self.nodes[right].distance_squared(point)
+                    {
+                        (left, right)
+                    } else {
+                        (right, left)
+                    };
+                    stack.extend([far, near]);
+                }
+                NodeKind::Leaf(start, end) => {
+                    for triangle in &self.triangles[start..end] {
+                        if !self.in_dimension(triangle, dimension) {
+                            continue;
+                        }
+                        let closest = triangle.closest_point(point);
+                        let distance = point.distance_squared(closest);
+                        if best.is_none_or(|(best, _, _)| distance < best) {
+                            best = Some((distance, closest, triangle));
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|(distance, closest, triangle)| SurfaceHit {
+            point: closest,
+            normal: triangle.normal(),
+            distance: distance.sqrt(),
+            dimension: self.dimensions[triangle.geometry],
+            geometry: triangle.geometry,
+        })
+    }
+
+    /// The surface a point stands on: the first one straight below it, or the
+    /// nearest one when nothing is below. Its dimension and geometry tell where
+    /// the point is.
+    pub fn locate(&self, point: Vec3, dimension: Option<u8>) -> Option<SurfaceHit> {
+        match self.raycast(point, Vec3::NEG_Y, f32::MAX, dimension) {
+            Some(hit) => Some(SurfaceHit {
+                point: hit.point,
+                normal: hit.normal,
+                distance: hit.distance,
+                dimension: hit.dimension,
+                geometry: hit.geometry,
+            }),
+            None => self.nearest(point, dimension),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A flat grid of `size` by `size` unit squares at height `y`.
+    fn floor(dimension: u8, size: u32, y: f32) -> Geometry {
+        let mut vertices = vec![];
+        for z in 0..=size {
+            for x in 0..=size {
+                vertices.push(Vec3::new(x as f32, y, z as f32));
+            }
+        }
+        let mut indices = vec![];
+        let row = size + 1;
+        for z in 0..size {
+            for x in 0..size {
+                let i = z * row + x;
+                indices.extend([i, i + row, i + 1, i + 1, i + row, i + row + 1]);
+            }
+        }
+        Geometry {
+            dimension,
+            vertices,
+            indices,
+        }
+    }
+
+    #[test]
+    fn raycasts_against_floor() {
+        let navmesh = NavMesh::new(&[floor(0, 16, 0.), floor(1, 16, 5.)]);
+
+        let hit = navmesh
+            .raycast(Vec3::new(3.3, 10., 7.6), Vec3::NEG_Y, 100., None)
+            .unwrap();
+        assert_eq!(hit.dimension, 1);
+        assert!((hit.distance - 5.).abs() < 1e-4);
+        assert!((hit.normal.y.abs() - 1.).abs() < 1e-4);
+
+        let hit = navmesh
+            .raycast(Vec3::new(3.3, 10., 7.6), Vec3::NEG_Y, 100., Some(0))
+            .unwrap();
+        assert_eq!(hit.dimension, 0);
+        assert!((hit.point - Vec3::new(3.3, 0., 7.6)).length() < 1e-4);
+
+        assert!(navmesh
+            .raycast(Vec3::new(3.3, 10., 7.6), Vec3::NEG_Y, 4., None)
+            .is_none());
+        assert!(navmesh
+            .raycast(Vec3::new(20., 10., 7.6), Vec3::NEG_Y, 100., None)
+            .is_none());
+    }
+
+    #[test]
+    fn finds_nearest_surface() {
+        let navmesh = NavMesh::new(&[floor(0, 16, 0.), floor(1, 16, 5.)]);
+
+        let hit = navmesh.nearest(Vec3::new(-2., 1., 4.5), None).unwrap();
+        assert_eq!(hit.dimension, 0);
+        assert!((hit.point - Vec3::new(0., 0., 4.5)).length() < 1e-4);
+        assert!((hit.distance - 5f32.sqrt()).abs() < 1e-4);
+
+        let hit = navmesh.nearest(Vec3::new(8., 4., 8.), None).unwrap();
+        assert_eq!(hit.dimension, 1);
+    }
+
+    #[test]
+    fn locates_point_on_surface_below() {
+        let navmesh = NavMesh::new(&[floor(0, 16, 0.), floor(1, 16, 5.)]);
+
+        let hit = navmesh.locate(Vec3::new(8., 4., 8.), None).unwrap();
+        assert_eq!((hit.dimension, hit.geometry), (0, 0));
+        let hit = navmesh.locate(Vec3::new(8.5, 6., 3.2), None).unwrap();
+        assert_eq!((hit.dimension, hit.geometry), (1, 1));
+        assert!(NavMesh::new(&[]).locate(Vec3::ZERO, None).is_none());
+    }
+
+    #[test]
+    fn skips_faces_outside_vertices() {
+        let navmesh = NavMesh::new(&[Geometry {
+            dimension: 0,
+            vertices: vec![Vec3::ZERO, Vec3::X, Vec3::Z],
+            indices: vec![0, 1, 2, 0, 1, 3],
+        }]);
+        assert_eq!(navmesh.triangles.len(), 1);
+        assert_eq!(navmesh.bounds(), Some((Vec3::ZERO, Vec3::new(1., 0., 1.))));
+    }
+}
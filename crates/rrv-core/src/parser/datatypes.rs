@@ -1,6 +1,10 @@
+use std::borrow::Cow;
+
 use glam::{DQuat, Vec3};
 use strum_macros::FromRepr;
 
+use super::Extensions;
+
 #[derive(Debug, Default)]
 pub struct Header<'a> {
     pub commons: Commons,
@@ -15,6 +19,29 @@ pub struct Header<'a> {
     pub disinfect_stations: Vec<DisinfectStation>,
     pub bulkhead_controllers: Vec<BulkheadController>,
     pub spitters: Vec<Spitter>,
+    /// Blocks stepped over through a [`BlockSize`](super::BlockSize) handler,
+    /// then the rest of the header from the first type without a handler.
+    pub unknown: Vec<RawBlock<'a>>,
+    /// Data of types decoded by handlers from other crates.
+    pub extensions: Extensions,
+}
+
+impl Header<'_> {
+    pub fn door(&self, id: i32) -> Option<&Door> {
+        self.doors.iter().find(|door| door.id == id)
+    }
+
+    /// Position and rotation of an object, from its index into [`Header::commons`].
+    pub fn common(&self, idx: usize) -> Option<&Common> {
+        self.commons.get(idx)
+    }
+}
+
+#[derive(Debug)]
+pub struct RawBlock<'a> {
+    pub typename: Cow<'a, str>,
+    pub version: Cow<'a, str>,
+    pub bytes: Cow<'a, [u8]>,
 }
 
 #[derive(FromRepr, Debug, PartialEq, Default)]
@@ -29,11 +56,11 @@ pub enum Identifier {
     Vanity,
 }
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum IdentifierType<'a> {
     #[default]
     Unknown,
-    Gear(&'a str, u16),
+    Gear(Cow<'a, str>, u16),
     AliasGear(u16),
     Item(u16),
     Enemy(u16),
@@ -42,11 +69,11 @@ pub enum IdentifierType<'a> {
 
 #[derive(Debug)]
 pub struct ReplayHeader<'a> {
-    pub version: &'a str,
+    pub version: Cow<'a, str>,
     pub master: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Common {
     pub dimension: u8,
     pub position: Vec3,
@@ -58,7 +85,7 @@ pub type Commons = Vec<Common>;
 // Covers both 0.0.1 and 0.0.2
 #[derive(Debug)]
 pub struct Metadata<'a> {
-    pub version: &'a str,
+    pub version: Cow<'a, str>,
     pub compatability_old_dc: Option<bool>,
 }
 
@@ -66,10 +93,10 @@ pub struct Metadata<'a> {
 pub struct Geometry {
     pub dimension: u8,
     pub vertices: Vec<Vec3>,
-    pub indices: Vec<u16>,
+    pub indices: Vec<u32>,
 }
 
-#[derive(FromRepr, Debug, PartialEq, Default)]
+#[derive(FromRepr, Debug, PartialEq, Eq, Hash, Default, Clone, Copy)]
 #[repr(u8)]
 pub enum DoorVariant {
     #[default]
@@ -80,7 +107,7 @@ pub enum DoorVariant {
     ApexDoor,
 }
 
-#[derive(FromRepr, Debug, PartialEq, Default)]
+#[derive(FromRepr, Debug, PartialEq, Eq, Hash, Default, Clone, Copy)]
 #[repr(u8)]
 pub enum DoorSize {
     #[default]
@@ -89,6 +116,32 @@ pub enum DoorSize {
     Large,
 }
 
+#[derive(FromRepr, Debug, PartialEq, Default, Clone, Copy)]
+#[repr(u8)]
+pub enum DoorState {
+    #[default]
+    Closed = 0,
+    Open,
+    Glued,
+    Destroyed,
+}
+
+/// What an enemy is doing, from asleep to chasing a player.
+///
+/// Provisional: the discriminants follow the order of the states in game and
+/// have not been checked against what the recorder writes, see
+/// [`TypeRegistry::register_provisional_enemies`](super::TypeRegistry::register_provisional_enemies).
+#[derive(FromRepr, Debug, PartialEq, Eq, Hash, Default, Clone, Copy)]
+#[repr(u8)]
+pub enum EnemyBehaviour {
+    #[default]
+    Hibernating = 0,
+    Patrolling,
+    Investigating,
+    Scouting,
+    Combat,
+}
+
 #[derive(Debug)]
 pub struct Door {
     pub id: i32,
@@ -155,6 +208,26 @@ pub struct BulkheadController {
     pub ovl: Option<i32>,
 }
 
+impl BulkheadController {
+    /// Resolves the door ids of the controller against the doors in `header`.
+    pub fn doors<'h>(&self, header: &'h Header<'_>) -> BulkheadDoors<'h> {
+        BulkheadDoors {
+            main: self.main.and_then(|id| header.door(id)),
+            secondary: self.secondary.and_then(|id| header.door(id)),
+            ovl: self.ovl.and_then(|id| header.door(id)),
+        }
+    }
+}
+
+/// Doors opened by a [`BulkheadController`] for the main, secondary and
+/// overload objectives.
+#[derive(Debug, Default)]
+pub struct BulkheadDoors<'h> {
+    pub main: Option<&'h Door>,
+    pub secondary: Option<&'h Door>,
+    pub ovl: Option<&'h Door>,
+}
+
 #[derive(Debug)]
 pub struct Spitter {
     pub id: i32,
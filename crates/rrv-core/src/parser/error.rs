@@ -0,0 +1,164 @@
+use std::fmt;
+
+use nom::{error::ParseError as NomParseError, IResult, Offset};
+
+use super::DataType;
+
+pub type PResult<'a, T> = IResult<&'a [u8], T, ParseError<'a>>;
+
+/// What went wrong while decoding.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    /// Type id that is not present in the typemap.
+    UnknownTypeId(u16),
+    /// Type that is present in the typemap but has no decoder.
+    UnhandledType,
+    /// Index of the dropped component of a half quaternion outside of `0..=3`.
+    InvalidQuatIndex(u8),
+    /// Element count that does not fit into `usize`.
+    InvalidLength(u32),
+    /// String that is not valid UTF-8.
+    InvalidUtf8,
+    /// Input ended in the middle of a block.
+    Eof,
+    /// Bytes left at the end of a size-prefixed frame after decoding it.
+    TrailingBytes(usize),
+    /// Any other failure reported by a nom combinator.
+    Nom(nom::error::ErrorKind),
+}
+
+/// Error type of the nom parsers. Keeps the input it failed at, so the byte
+/// offset can be recovered once the start of the replay is known.
+#[derive(Debug, PartialEq)]
+pub struct ParseError<'a> {
+    pub input: &'a [u8],
+    pub kind: ErrorKind,
+    /// Typename and version of the block that was being decoded.
+    pub datatype: Option<(String, String)>,
+}
+
+impl<'a> ParseError<'a> {
+    pub fn new(input: &'a [u8], kind: ErrorKind) -> Self {
+        Self {
+            input,
+            kind,
+            datatype: None,
+        }
+    }
+
+    pub fn err<T>(input: &'a [u8], kind: ErrorKind) -> PResult<'a, T> {
+        Err(nom::Err::Error(Self::new(input, kind)))
+    }
+
+    /// Attaches `t` unless a more specific block was already recorded.
+    pub fn in_datatype(mut self, t: &DataType) -> Self {
+        self.datatype
+            .get_or_insert_with(|| (t.typename.to_string(), t.version.to_string()));
+        self
+    }
+}
+
+impl<'a> NomParseError<&'a [u8]> for ParseError<'a> {
+    fn from_error_kind(input: &'a [u8], kind: nom::error::ErrorKind) -> Self {
+        let kind = match kind {
+            nom::error::ErrorKind::Eof => ErrorKind::Eof,
+            kind => ErrorKind::Nom(kind),
+        };
+        Self::new(input, kind)
+    }
+
+    fn append(_: &'a [u8], _: nom::error::ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+/// Tags an error in `r` with the typename and version of `t`.
+pub fn in_datatype<'a, T>(t: &DataType, r: PResult<'a, T>) -> PResult<'a, T> {
+    r.map_err(|e| e.map(|e| e.in_datatype(t)))
+}
+
+/// Error returned when a replay can not be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// Input ended before the replay was complete.
+    Incomplete { offset: usize },
+    /// Input is not a valid replay.
+    Malformed {
+        /// Byte offset from the start of the replay.
+        offset: usize,
+        typename: Option<String>,
+        version: Option<String>,
+        kind: ErrorKind,
+    },
+}
+
+impl ReplayError {
+    /// Converts a parser error, computing offsets relative to `start`.
+    pub fn from_nom(start: &[u8], e: nom::Err<ParseError>) -> Self {
+        match e {
+            nom::Err::Incomplete(_) => Self::Incomplete {
+                offset: start.len(),
+            },
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                let (typename, version) = e.datatype.unzip();
+                Self::Malformed {
+                    offset: start.offset(e.input),
+                    typename,
+                    version,
+                    kind: e.kind,
+                }
+            }
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        match self {
+            Self::Incomplete { offset } | Self::Malformed { offset, .. } => *offset,
+        }
+    }
+
+    /// Moves the offset `n` bytes further, for errors found in a slice of the replay.
+    pub(super) fn offset_by(mut self, n: usize) -> Self {
+        match &mut self {
+            Self::Incomplete { offset } | Self::Malformed { offset, .. } => *offset += n,
+        }
+        self
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownTypeId(id) => write!(f, "unknown type id {id}"),
+            Self::UnhandledType => write!(f, "no decoder for type"),
+            Self::InvalidQuatIndex(idx) => write!(f, "invalid half quaternion index {idx}"),
+            Self::InvalidLength(n) => write!(f, "invalid length {n}"),
+            Self::InvalidUtf8 => write!(f, "invalid utf-8 string"),
+            Self::Eof => write!(f, "unexpected end of block"),
+            Self::TrailingBytes(n) => write!(f, "{n} bytes left at the end of the frame"),
+            Self::Nom(kind) => write!(f, "{}", kind.description()),
+        }
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Incomplete { offset } => write!(f, "replay ends early at byte {offset}"),
+            Self::Malformed {
+                offset,
+                typename,
+                version,
+                kind,
+            } => {
+                write!(f, "{kind} at byte {offset}")?;
+                if let (Some(typename), Some(version)) = (typename, version) {
+                    write!(f, " in {typename} {version}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
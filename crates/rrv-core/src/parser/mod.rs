@@ -1,77 +1,102 @@
 mod datatypes;
+mod error;
+mod owned;
+mod registry;
+mod stream;
 mod types;
+mod vanilla;
 
 use core::str;
+use std::sync::OnceLock;
 
 pub use datatypes::*;
+pub use error::*;
 use glam::{DQuat, Vec3};
-use log::info;
 use nom::{
     bytes::complete::take,
-    combinator::map_res,
     multi::count,
     number::complete::{le_f32, le_i32, le_u16, le_u32, le_u8},
     sequence::{pair, tuple},
-    IResult,
 };
+pub use owned::*;
+pub use registry::*;
+pub use stream::*;
 pub use types::*;
+pub use vanilla::decode_geometry_u32;
 
 #[derive(Debug)]
 pub struct Replay<'a> {
     pub typemap: Typemap<'a>,
     pub header: Header<'a>,
+    pub snapshots: Vec<Snapshot>,
 }
 
-pub fn parse_replay(i: &[u8]) -> IResult<&[u8], Replay> {
-    let (i, (typemap, header)) = parse_replay_file_commons(i)?;
+fn vanilla_registry() -> &'static TypeRegistry {
+    static VANILLA: OnceLock<TypeRegistry> = OnceLock::new();
+    VANILLA.get_or_init(TypeRegistry::vanilla)
+}
 
-    Ok((i, Replay { typemap, header }))
+pub fn parse_replay(input: &[u8]) -> Result<Replay<'_>, ReplayError> {
+    parse_replay_with(input, vanilla_registry())
 }
 
-pub fn parse_replay_file_commons(i: &[u8]) -> IResult<&[u8], (Typemap, Header)> {
+/// Same as [`parse_replay`], decoding header types through `registry`.
+pub fn parse_replay_with<'a>(
+    input: &'a [u8],
+    registry: &TypeRegistry,
+) -> Result<Replay<'a>, ReplayError> {
+    let parse = |i| -> PResult<'_, Replay<'_>> {
+        let (mut i, (typemap, header)) = parse_replay_file_commons(i, registry)?;
+
+        let mut snapshots = vec![];
+        while !i.is_empty() {
+            let snapshot;
+            (i, snapshot) = parse_snapshot(i, &typemap, registry)?;
+            snapshots.push(snapshot);
+        }
+
+        Ok((
+            i,
+            Replay {
+                typemap,
+                header,
+                snapshots,
+            },
+        ))
+    };
+
+    parse(input)
+        .map(|(_, replay)| replay)
+        .map_err(|e| ReplayError::from_nom(input, e))
+}
+
+pub fn parse_replay_file_commons<'a>(
+    i: &'a [u8],
+    registry: &TypeRegistry,
+) -> PResult<'a, (Typemap<'a>, Header<'a>)> {
     let (i, metadata_size) = le_u32(i)?;
     let (i, typemap_and_header_bytes) = take(metadata_size)(i)?;
-    let (leftover, (typemap, header)) = parse_typemap_and_header(typemap_and_header_bytes)?;
-
-    // info!(
-    //     "{:#?}\n{:#?}",
-    //     header.bulkhead_controllers.first().unwrap(),
-    //     header.commons[header.bulkhead_controllers.first().unwrap().idx]
-    // );
-    // let bulkhead_door = header
-    //     .doors
-    //     .iter()
-    //     .find(|&v| {
-    //         v.id == header
-    //             .bulkhead_controllers
-    //             .first()
-    //             .unwrap()
-    //             .secondary
-    //             .unwrap()
-    //     })
-    //     .unwrap();
-    // info!(
-    //     "{:#?}\n{:#?}",
-    //     bulkhead_door, header.commons[bulkhead_door.idx]
-    // );
-    // info!("{:#?}", header.level_geometry.first().unwrap().vertices);
-    // info!("{:?}", leftover);
+    let (_, (typemap, header)) = parse_typemap_and_header(typemap_and_header_bytes, registry)?;
 
     Ok((i, (typemap, header)))
 }
 
-pub fn parse_replay_string(i: &[u8]) -> IResult<&[u8], &str> {
+pub fn parse_replay_string(i: &[u8]) -> PResult<'_, &str> {
     let (i, length) = le_u16(i)?;
-    map_res(take(length), str::from_utf8)(i)
+    let (rest, bytes) = take(length)(i)?;
+    match str::from_utf8(bytes) {
+        Ok(s) => Ok((rest, s)),
+        Err(_) => ParseError::err(i, ErrorKind::InvalidUtf8),
+    }
 }
 
-pub fn parse_replay_bool(i: &[u8]) -> IResult<&[u8], bool> {
+pub fn parse_replay_bool(i: &[u8]) -> PResult<'_, bool> {
     let (i, val) = le_u8(i)?;
 
     Ok((i, val > 0))
 }
 
-pub fn parse_replay_identifier_type(i: &[u8]) -> IResult<&[u8], IdentifierType> {
+pub fn parse_replay_identifier_type(i: &[u8]) -> PResult<'_, IdentifierType<'_>> {
     let (mut i, val) = le_u8(i)?;
     let idt = match Identifier::from_repr(val).unwrap_or_default() {
         Identifier::Unknown => IdentifierType::Unknown,
@@ -79,7 +104,7 @@ pub fn parse_replay_identifier_type(i: &[u8]) -> IResult<&[u8], IdentifierType>
             let gear;
             let alias;
             (i, (gear, alias)) = pair(parse_replay_string, le_u16)(i)?;
-            IdentifierType::Gear(gear, alias)
+            IdentifierType::Gear(gear.into(), alias)
         }
         Identifier::AliasGear => {
             let alias;
@@ -106,14 +131,14 @@ pub fn parse_replay_identifier_type(i: &[u8]) -> IResult<&[u8], IdentifierType>
     Ok((i, idt))
 }
 
-pub fn parse_vec3(i: &[u8]) -> IResult<&[u8], Vec3> {
+pub fn parse_vec3(i: &[u8]) -> PResult<'_, Vec3> {
     let (i, (x, y, z)) = tuple((le_f32, le_f32, le_f32))(i)?;
     Ok((i, Vec3::new(x, y, -z)))
 }
 
 pub type BulkheadLayers = (Option<i32>, Option<i32>, Option<i32>);
 
-pub fn parse_bulkhead_dc(i: &[u8]) -> IResult<&[u8], BulkheadLayers> {
+pub fn parse_bulkhead_dc(i: &[u8]) -> PResult<'_, BulkheadLayers> {
     let (mut main_door_id, mut secondary_door_id, mut overload_door_id) = (None, None, None);
     let (mut i, main) = parse_replay_bool(i)?;
     if main {
@@ -139,14 +164,14 @@ pub fn parse_bulkhead_dc(i: &[u8]) -> IResult<&[u8], BulkheadLayers> {
     Ok((i, (main_door_id, secondary_door_id, overload_door_id)))
 }
 
-pub fn le_f16(i: &[u8]) -> IResult<&[u8], f16> {
+pub fn le_f16(i: &[u8]) -> PResult<'_, f16> {
     let (i, bits) = le_u16(i)?;
     // Unity is left-handed y up, bevy is right-handed y up
     Ok((i, f16::from_bits(bits)))
 }
 
-pub fn parse_half_quat(i: &[u8]) -> IResult<&[u8], DQuat> {
-    let (i, (idx, a, b, c)) = tuple((le_u8, le_f16, le_f16, le_f16))(i)?;
+pub fn parse_half_quat(input: &[u8]) -> PResult<'_, DQuat> {
+    let (i, (idx, a, b, c)) = tuple((le_u8, le_f16, le_f16, le_f16))(input)?;
     let (x, y, z, w) = match idx {
         0 => {
             let (y, z, w): (f64, f64, f64) = (a.into(), b.into(), c.into());
@@ -168,13 +193,14 @@ pub fn parse_half_quat(i: &[u8]) -> IResult<&[u8], DQuat> {
             let w = f64::sqrt((1. - x.powi(2) - y.powi(2) - z.powi(2)).clamp(0., 1.));
             (x, y, z, w)
         }
-        _ => unreachable!(),
+        _ => return ParseError::err(input, ErrorKind::InvalidQuatIndex(idx)),
     };
-    // Unity is left-handed y up, bevy is right-handed y up
-    Ok((i, DQuat::from_xyzw(x, y, -z, w)))
+    // Unity is left-handed y up, bevy is right-handed y up. Mirroring z like
+    // `parse_vec3` flips the x and y axes of the rotation.
+    Ok((i, DQuat::from_xyzw(-x, -y, z, w)))
 }
 
-pub fn parse_commons(i: &[u8]) -> IResult<&[u8], Common> {
+pub fn parse_commons(i: &[u8]) -> PResult<'_, Common> {
     let (i, (dimension, position, rotation)) = tuple((le_u8, parse_vec3, parse_half_quat))(i)?;
 
     Ok((
@@ -187,7 +213,7 @@ pub fn parse_commons(i: &[u8]) -> IResult<&[u8], Common> {
     ))
 }
 
-pub fn parse_datatype(i: &[u8]) -> IResult<&[u8], DataType> {
+pub fn parse_datatype(i: &[u8]) -> PResult<'_, DataType<'_>> {
     let (i, (id, typename, version)) =
         tuple((le_u16, parse_replay_string, parse_replay_string))(i)?;
 
@@ -195,339 +221,796 @@ pub fn parse_datatype(i: &[u8]) -> IResult<&[u8], DataType> {
         i,
         DataType {
             id,
-            typename,
-            version,
+            typename: typename.into(),
+            version: version.into(),
         },
     ))
 }
 
-pub fn parse_typemap_and_header(i: &[u8]) -> IResult<&[u8], (Typemap, Header)> {
-    let (i, (typemap_ver, typemap_entries)) = pair(parse_replay_string, le_u16)(i)?;
-    let (mut i, types) = count(parse_datatype, typemap_entries.into())(i)?;
+pub fn parse_typemap(i: &[u8]) -> PResult<'_, Typemap<'_>> {
+    let (i, (version, entries)) = pair(parse_replay_string, le_u16)(i)?;
+    let (i, types) = count(parse_datatype, entries.into())(i)?;
 
-    let typemap = Typemap {
-        version: typemap_ver,
-        entries: typemap_entries,
-        types,
-    };
+    Ok((
+        i,
+        Typemap {
+            version: version.into(),
+            entries,
+            types,
+        },
+    ))
+}
+
+pub fn parse_typemap_and_header<'a>(
+    i: &'a [u8],
+    registry: &TypeRegistry,
+) -> PResult<'a, (Typemap<'a>, Header<'a>)> {
+    let (mut i, typemap) = parse_typemap(i)?;
 
     let mut header = Header::default();
 
     loop {
+        let start = i;
         let id;
         (i, id) = le_u16(i)?;
-        let t = typemap.types.iter().find(|&v| v.id == id).unwrap();
-        // info!("{:#?}", t);
-        match t {
-            DataType {
-                typename: "ReplayRecorder.Header", // string, bool
-                ..
-            } => {
-                let version;
-                let master;
-                (i, (version, master)) = pair(parse_replay_string, parse_replay_bool)(i)?;
-                // info!("{:?} {:?}", version, master);
-                header.replay_header = Some(ReplayHeader { version, master });
-            }
-            DataType {
-                typename: "ReplayRecorder.EndOfHeader",
-                ..
-            } => break,
-            DataType {
-                typename: "Vanilla.Metadata", // string
-                version: "0.0.1",
-                ..
-            } => {
-                let version;
-                (i, version) = parse_replay_string(i)?;
-                // info!("{:?}", version);
-                header.metadata = Some(Metadata {
-                    version,
-                    compatability_old_dc: None,
-                });
-            }
-            DataType {
-                typename: "Vanilla.Metadata", // string, bool
-                version: "0.0.2",
-                ..
-            } => {
-                let version;
-                let compat;
-                (i, (version, compat)) = pair(parse_replay_string, parse_replay_bool)(i)?;
-                // info!("{:?} {:?}", version, compat);
-                header.metadata = Some(Metadata {
-                    version,
-                    compatability_old_dc: Some(compat),
-                });
-            }
-            DataType {
-                typename: "Vanilla.Map.Geometry", // u8, u16, u32, f32 * 3 list, u16 list
-                ..
-            } => {
-                let dimension;
-                let num_vert;
-                let num_idx;
-                let vertices;
-                let indices;
-                (i, (dimension, num_vert, num_idx)) = tuple((le_u8, le_u16, le_u32))(i)?;
-                // info!("{:?} {:?} {:?}", dimension, num_vert, num_idx);
-                (i, vertices) = count(parse_vec3, num_vert.into())(i)?;
-                (i, indices) = count(le_u16, usize::try_from(num_idx).unwrap())(i)?;
-                header.level_geometry.push(Geometry {
-                    dimension,
-                    vertices,
-                    indices,
-                });
+        let t = lookup_datatype(start, &typemap, id)?;
+        if t.typename == "ReplayRecorder.EndOfHeader" {
+            break;
+        }
+        if registry.get(&t.typename, &t.version).is_none() {
+            // Header blocks are not size-prefixed, so there is no telling where
+            // the next one starts. Keep the rest of the header frame instead.
+            header.unknown.push(RawBlock {
+                typename: t.typename.clone(),
+                version: t.version.clone(),
+                bytes: i.into(),
+            });
+            return Ok((&i[i.len()..], (typemap, header)));
+        }
+        (i, ()) = in_datatype(t, parse_header_block(i, t, registry, &mut header))?;
+    }
+
+    Ok((i, (typemap, header)))
+}
+
+pub fn parse_header_block<'a>(
+    i: &'a [u8],
+    t: &DataType<'a>,
+    registry: &TypeRegistry,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    match registry.get(&t.typename, &t.version) {
+        Some(handler) => handler.decode(i, t, header),
+        None => ParseError::err(i, ErrorKind::UnhandledType),
+    }
+}
+
+pub fn parse_snapshot<'a>(
+    i: &'a [u8],
+    typemap: &Typemap,
+    registry: &TypeRegistry,
+) -> PResult<'a, Snapshot> {
+    let (i, size) = le_u32(i)?;
+    let (i, snapshot_bytes) = take(size)(i)?;
+    let (_, snapshot) = parse_snapshot_body(snapshot_bytes, typemap, registry)?;
+
+    Ok((i, snapshot))
+}
+
+/// Decodes the contents of one snapshot frame, which must be used up entirely.
+pub fn parse_snapshot_body<'a>(
+    i: &'a [u8],
+    typemap: &Typemap,
+    registry: &TypeRegistry,
+) -> PResult<'a, Snapshot> {
+    // u32, u32, (u16, u16, ...) list, u16, (u16, u32, (i32, ...) list) list
+    let (mut i, (timestamp, num_events)) = pair(le_u32, le_u32)(i)?;
+    let mut snapshot = Snapshot {
+        timestamp,
+        events: vec![],
+        dynamics: vec![],
+        undecoded: None,
+    };
+
+    for _ in 0..num_events {
+        let start = i;
+        let offset;
+        let id;
+        (i, (offset, id)) = pair(le_u16, le_u16)(i)?;
+        let t = lookup_datatype(start, typemap, id)?;
+        let Some(handler) = registry.event(&t.typename, &t.version) else {
+            snapshot.undecoded = Some(undecoded(t, i));
+            return Ok((&i[i.len()..], snapshot));
+        };
+        let data;
+        (i, data) = match handler.decode(i, t, typemap) {
+            Err(e) if is_unhandled(&e) => {
+                snapshot.undecoded = Some(undecoded(t, i));
+                return Ok((&i[i.len()..], snapshot));
             }
-            DataType {
-                typename: "Vanilla.Map.Geometry.EOH",
-                ..
-            } => {}
-            DataType {
-                typename: "Vanilla.Map.Doors", // u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool, u8, u8)
-                ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(
-                    pair(
-                        tuple((le_i32, parse_commons)),
-                        tuple((le_u16, parse_replay_bool, le_u8, le_u8)),
-                    ),
-                    n.into(),
-                )(i)?;
-                for ((id, common), (serial, checkpoint, variant, size)) in items {
-                    header.doors.push(Door {
-                        id,
-                        idx: header.commons.len(),
-                        serial,
-                        checkpoint,
-                        variant: DoorVariant::from_repr(variant).unwrap_or_default(),
-                        size: DoorSize::from_repr(size).unwrap_or_default(),
-                    });
-                    header.commons.push(common);
+            r => in_datatype(t, r)?,
+        };
+        snapshot.events.push(Event { id, offset, data });
+    }
+
+    let num_collections;
+    (i, num_collections) = le_u16(i)?;
+    for _ in 0..num_collections {
+        let start = i;
+        let type_id;
+        (i, type_id) = le_u16(i)?;
+        let t = lookup_datatype(start, typemap, type_id)?;
+        let Some(handler) = registry.dynamic(&t.typename, &t.version) else {
+            snapshot.undecoded = Some(undecoded(t, i));
+            return Ok((&i[i.len()..], snapshot));
+        };
+        let n;
+        (i, n) = le_u32(i)?;
+        for _ in 0..n {
+            let id;
+            let data;
+            (i, id) = le_i32(i)?;
+            (i, data) = match handler.decode(i, t) {
+                Err(e) if is_unhandled(&e) => {
+                    snapshot.undecoded = Some(undecoded(t, i));
+                    return Ok((&i[i.len()..], snapshot));
                 }
-            }
-            DataType {
-                typename: "Vanilla.Map.Ladders", // u16, (u8, f32 * 3, f16 * 3 + u8, f16)
+                r => in_datatype(t, r)?,
+            };
+            snapshot.dynamics.push(Dynamic { type_id, id, data });
+        }
+    }
+
+    if !i.is_empty() {
+        return ParseError::err(i, ErrorKind::TrailingBytes(i.len()));
+    }
+
+    Ok((i, snapshot))
+}
+
+/// Whether a handler gave up on `e` because it doesn't know the layout, which
+/// ends the snapshot like a missing handler.
+fn is_unhandled(e: &nom::Err<ParseError>) -> bool {
+    matches!(e, nom::Err::Error(e) if e.kind == ErrorKind::UnhandledType)
+}
+
+fn undecoded(t: &DataType, rest: &[u8]) -> RawBlock<'static> {
+    RawBlock {
+        typename: t.typename.to_string().into(),
+        version: t.version.to_string().into(),
+        bytes: rest.to_vec().into(),
+    }
+}
+
+fn lookup_datatype<'a, 'b, 't>(
+    i: &'a [u8],
+    typemap: &'b Typemap<'t>,
+    id: u16,
+) -> Result<&'b DataType<'t>, nom::Err<ParseError<'a>>> {
+    typemap
+        .get(id)
+        .ok_or_else(|| nom::Err::Error(ParseError::new(i, ErrorKind::UnknownTypeId(id))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little-endian writer for building replay files by hand.
+    #[derive(Default)]
+    pub(crate) struct Writer(pub Vec<u8>);
+
+    impl Writer {
+        pub fn u8(&mut self, v: u8) -> &mut Self {
+            self.0.push(v);
+            self
+        }
+
+        pub fn u16(&mut self, v: u16) -> &mut Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        pub fn u32(&mut self, v: u32) -> &mut Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        pub fn u64(&mut self, v: u64) -> &mut Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        pub fn i32(&mut self, v: i32) -> &mut Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        pub fn f32(&mut self, v: f32) -> &mut Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        pub fn string(&mut self, v: &str) -> &mut Self {
+            self.u16(v.len() as u16);
+            self.0.extend_from_slice(v.as_bytes());
+            self
+        }
+
+        pub fn common(&mut self, dimension: u8, position: [f32; 3]) -> &mut Self {
+            self.u8(dimension)
+                .f32(position[0])
+                .f32(position[1])
+                .f32(position[2]);
+            // identity rotation, w is the dropped component
+            self.u8(3).u16(0).u16(0).u16(0)
+        }
+
+        /// Appends `body` prefixed with its length as u32.
+        pub fn block(&mut self, body: &Writer) -> &mut Self {
+            self.u32(body.0.len() as u32);
+            self.0.extend_from_slice(&body.0);
+            self
+        }
+    }
+
+    pub(crate) const TYPES: &[(u16, &str, &str)] = &[
+        (0, "ReplayRecorder.Header", "0.0.1"),
+        (1, "ReplayRecorder.EndOfHeader", "0.0.1"),
+        (2, "ReplayRecorder.Spawn", "0.0.1"),
+        (3, "ReplayRecorder.Despawn", "0.0.1"),
+        (4, "Vanilla.Map.DoorStatusChange", "0.0.1"),
+        (5, "Vanilla.Player", "0.0.1"),
+        (6, "Vanilla.Map.Ladders", "0.0.1"),
+        (7, "Modded.Event", "1.0.0"),
+    ];
+
+    pub(crate) fn header_block() -> Writer {
+        let mut w = Writer::default();
+        w.string("0.0.1").u16(TYPES.len() as u16);
+        for (id, typename, version) in TYPES {
+            w.u16(*id).string(typename).string(version);
+        }
+        w.u16(0).string("0.0.1").u8(1);
+        w.u16(6).u16(1).common(0, [1., 2., 3.]).u16(0x3c00);
+        w.u16(1);
+        w
+    }
+
+    pub(crate) fn snapshot_block(timestamp: u32, x: f32) -> Writer {
+        let mut w = Writer::default();
+        w.u32(timestamp).u32(2);
+        w.u16(0).u16(2).u16(5).i32(7).common(0, [0., 0., 0.]);
+        w.u16(10).u16(4).i32(42).u8(1);
+        w.u16(1).u16(5).u32(1).i32(7).common(1, [x, 0., 0.]);
+        w
+    }
+
+    pub(crate) fn replay_bytes() -> Vec<u8> {
+        let mut w = Writer::default();
+        w.block(&header_block())
+            .block(&snapshot_block(0, 1.))
+            .block(&snapshot_block(100, 2.));
+        w.0
+    }
+
+    #[test]
+    fn parses_header_and_snapshots() {
+        let bytes = replay_bytes();
+        let replay = parse_replay(&bytes).unwrap();
+
+        assert!(replay.header.replay_header.unwrap().master);
+        assert_eq!(replay.header.ladders.len(), 1);
+        assert_eq!(replay.snapshots.len(), 2);
+
+        let snapshot = &replay.snapshots[1];
+        assert_eq!(snapshot.timestamp, 100);
+        assert!(matches!(
+            snapshot.events[0].data,
+            EventData::Spawn {
+                type_id: 5,
+                id: 7,
                 ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(pair(parse_commons, le_f16), n.into())(i)?;
-                for (common, height) in items {
-                    header.ladders.push(Ladder {
-                        idx: header.commons.len(),
-                        height,
-                    });
-                    header.commons.push(common);
-                }
             }
-            DataType {
-                typename: "Vanilla.Map.Terminals", // u16, (i32, u8, f32 * 3, f16 * 3 + u8)
-                ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(tuple((le_i32, parse_commons)), n.into())(i)?;
-                for (id, common) in items {
-                    header.terminals.push(Terminal {
-                        id,
-                        idx: header.commons.len(),
-                    });
-                    header.commons.push(common);
-                }
+        ));
+        assert_eq!(snapshot.events[1].offset, 10);
+        assert!(matches!(
+            snapshot.events[1].data,
+            EventData::DoorStatus {
+                id: 42,
+                state: DoorState::Open
             }
-            DataType {
-                typename: "Vanilla.Map.Generators", // u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16)
+        ));
+        let DynamicData::Transform(common) = &snapshot.dynamics[0].data else {
+            panic!("expected a transform");
+        };
+        assert_eq!(snapshot.dynamics[0].id, 7);
+        assert_eq!(common.dimension, 1);
+        assert_eq!(common.position, Vec3::new(2., 0., 0.));
+    }
+
+    #[test]
+    fn owned_replay_outlives_input() {
+        let replay: ReplayOwned = {
+            let bytes = replay_bytes();
+            parse_replay(&bytes).unwrap().into_owned()
+        };
+
+        assert_eq!(replay.typemap.get(5).unwrap().typename, "Vanilla.Player");
+        assert_eq!(replay.header.replay_header.unwrap().version, "0.0.1");
+        assert_eq!(replay.snapshots.len(), 2);
+    }
+
+    /// Snapshot with a door event, a modded event carrying an i32, and a
+    /// player transform.
+    fn modded_snapshot_block(timestamp: u32) -> Writer {
+        let mut w = Writer::default();
+        w.u32(timestamp).u32(2);
+        w.u16(0).u16(4).i32(42).u8(1);
+        w.u16(0).u16(7).i32(5);
+        w.u16(1).u16(5).u32(1).i32(7).common(0, [0., 0., 0.]);
+        w
+    }
+
+    #[test]
+    fn keeps_rest_of_snapshot_after_unhandled_event() {
+        let mut w = Writer::default();
+        w.block(&header_block())
+            .block(&modded_snapshot_block(0))
+            .block(&snapshot_block(100, 2.));
+
+        let replay = parse_replay(&w.0).unwrap();
+
+        assert_eq!(replay.snapshots.len(), 2);
+        let snapshot = &replay.snapshots[0];
+        assert_eq!(snapshot.events.len(), 1);
+        assert!(snapshot.dynamics.is_empty());
+        let undecoded = snapshot.undecoded.as_ref().unwrap();
+        assert_eq!(undecoded.typename, "Modded.Event");
+        // The modded event, then the dynamics it hides.
+        assert_eq!(undecoded.bytes[..4], 5i32.to_le_bytes());
+        assert_eq!(undecoded.bytes.len(), 4 + 2 + 2 + 4 + 4 + 20);
+        assert!(replay.snapshots[1].undecoded.is_none());
+        assert_eq!(replay.snapshots[1].dynamics.len(), 1);
+    }
+
+    struct ModdedEvent(i32);
+
+    fn decode_modded_event<'a, 't>(
+        i: &'a [u8],
+        _: &DataType<'t>,
+        _: &Typemap<'t>,
+    ) -> PResult<'a, EventData> {
+        let (i, value) = le_i32(i)?;
+        Ok((i, EventData::Extension(Box::new(ModdedEvent(value)))))
+    }
+
+    #[test]
+    fn decodes_registered_custom_events() {
+        let mut w = Writer::default();
+        w.block(&header_block()).block(&modded_snapshot_block(0));
+        let mut registry = TypeRegistry::vanilla();
+        registry.register_event_any("Modded.Event", decode_modded_event);
+
+        let replay = parse_replay_with(&w.0, &registry).unwrap();
+
+        let snapshot = &replay.snapshots[0];
+        assert!(snapshot.undecoded.is_none());
+        assert_eq!(snapshot.dynamics.len(), 1);
+        let EventData::Extension(data) = &snapshot.events[1].data else {
+            panic!("expected an extension event");
+        };
+        assert_eq!(data.downcast_ref::<ModdedEvent>().unwrap().0, 5);
+    }
+
+    #[test]
+    fn rejects_trailing_bytes_in_snapshot() {
+        let mut snapshot = snapshot_block(0, 1.);
+        snapshot.u8(0).u8(0);
+        let mut w = Writer::default();
+        w.block(&header_block()).block(&snapshot);
+
+        assert!(matches!(
+            parse_replay(&w.0).unwrap_err(),
+            ReplayError::Malformed {
+                kind: ErrorKind::TrailingBytes(2),
                 ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(pair(tuple((le_i32, parse_commons)), le_u16), n.into())(i)?;
-                for ((id, common), serial) in items {
-                    header.generators.push(Generator {
-                        id,
-                        idx: header.commons.len(),
-                        serial,
-                    });
-                    header.commons.push(common);
-                }
             }
-            DataType {
-                typename: "Vanilla.Map.DisinfectStations", // u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16)
-                ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(pair(tuple((le_i32, parse_commons)), le_u16), n.into())(i)?;
-                for ((id, common), serial) in items {
-                    header.disinfect_stations.push(DisinfectStation {
-                        id,
-                        idx: header.commons.len(),
-                        serial,
-                    });
-                    header.commons.push(common);
-                }
+        ));
+    }
+
+    #[test]
+    fn reports_datatype_of_malformed_block() {
+        let mut header = header_block();
+        // ladder with an invalid half quaternion index, before EndOfHeader
+        let len = header.0.len();
+        header.0.truncate(len - 2);
+        header.u16(6).u16(1).u8(0).f32(0.).f32(0.).f32(0.);
+        let quat_offset = 4 + header.0.len();
+        header.u8(7).u16(0).u16(0).u16(0).u16(0).u16(1);
+        let mut w = Writer::default();
+        w.block(&header);
+
+        assert_eq!(
+            parse_replay(&w.0).unwrap_err(),
+            ReplayError::Malformed {
+                offset: quat_offset,
+                typename: Some("Vanilla.Map.Ladders".to_string()),
+                version: Some("0.0.1".to_string()),
+                kind: ErrorKind::InvalidQuatIndex(7),
             }
-            DataType {
-                typename: "Vanilla.Map.BulkheadControllers", // u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool, i32?, bool, i32?, bool, i32?)
+        );
+    }
+
+    fn modded_header() -> Writer {
+        let mut w = Writer::default();
+        w.string("0.0.1").u16(3);
+        w.u16(0).string("ReplayRecorder.Header").string("0.0.1");
+        w.u16(1)
+            .string("ReplayRecorder.EndOfHeader")
+            .string("0.0.1");
+        w.u16(2).string("Modded.Thing").string("1.0.0");
+        w.u16(2).u16(2).i32(1).i32(2);
+        w.u16(0).string("0.0.1").u8(0);
+        w.u16(1);
+        w
+    }
+
+    #[test]
+    fn skips_registered_unknown_types() {
+        let mut w = Writer::default();
+        w.block(&modded_header());
+        let mut registry = TypeRegistry::vanilla();
+        registry.register("Modded.Thing", "1.0.0", BlockSize::Counted(4));
+
+        let replay = parse_replay_with(&w.0, &registry).unwrap();
+
+        assert!(replay.header.replay_header.is_some());
+        let block = &replay.header.unknown[0];
+        assert_eq!(block.typename, "Modded.Thing");
+        assert_eq!(*block.bytes, [2, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+    }
+
+    #[derive(Default)]
+    struct Things(Vec<i32>);
+
+    fn decode_things<'a>(
+        i: &'a [u8],
+        _: &DataType<'a>,
+        header: &mut Header<'a>,
+    ) -> PResult<'a, ()> {
+        let (i, n) = le_u16(i)?;
+        let (i, items) = count(le_i32, n.into())(i)?;
+        header.extensions.get_or_default::<Things>().0.extend(items);
+
+        Ok((i, ()))
+    }
+
+    #[test]
+    fn decodes_registered_custom_types() {
+        let mut w = Writer::default();
+        w.block(&modded_header());
+        let mut registry = TypeRegistry::vanilla();
+        registry.register_any("Modded.Thing", decode_things);
+
+        let replay = parse_replay_with(&w.0, &registry).unwrap();
+
+        assert_eq!(replay.header.extensions.get::<Things>().unwrap().0, [1, 2]);
+        assert!(replay.header.unknown.is_empty());
+    }
+
+    #[test]
+    fn keeps_rest_of_header_after_unregistered_type() {
+        let mut snapshot = Writer::default();
+        snapshot.u32(0).u32(0).u16(0);
+        let mut w = Writer::default();
+        w.block(&modded_header()).block(&snapshot);
+
+        let replay = parse_replay(&w.0).unwrap();
+
+        // The replay header comes after the unknown block.
+        assert!(replay.header.replay_header.is_none());
+        let block = &replay.header.unknown[0];
+        assert_eq!(block.typename, "Modded.Thing");
+        assert_eq!(block.bytes[..10], [2, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(block.bytes.len(), 10 + 2 + 7 + 1 + 2);
+        assert_eq!(replay.snapshots.len(), 1);
+    }
+
+    #[test]
+    fn reports_truncated_snapshot() {
+        let mut bytes = replay_bytes();
+        bytes.truncate(bytes.len() - 3);
+
+        assert!(matches!(
+            parse_replay(&bytes).unwrap_err(),
+            ReplayError::Malformed {
+                kind: ErrorKind::Eof,
                 ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(
-                    pair(
-                        tuple((le_i32, parse_commons)),
-                        tuple((le_u16, parse_bulkhead_dc)),
-                    ),
-                    n.into(),
-                )(i)?;
-                for ((id, common), (serial, (main, secondary, ovl))) in items {
-                    header.bulkhead_controllers.push(BulkheadController {
-                        id,
-                        idx: header.commons.len(),
-                        serial,
-                        main,
-                        secondary,
-                        ovl,
-                    });
-                    header.commons.push(common);
-                }
             }
-            DataType {
-                typename: "Vanilla.Map.ResourceContainers", // u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool)
-                version: "0.0.1",
-                ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(
-                    pair(
-                        tuple((le_i32, parse_commons)),
-                        tuple((le_u16, parse_replay_bool)),
-                    ),
-                    n.into(),
-                )(i)?;
-                for ((id, common), (serial, locker)) in items {
-                    header.resource_containers.push(ResourceContainer {
-                        id,
-                        idx: header.commons.len(),
-                        serial,
-                        locker,
-                        consumable_type: None,
-                        registered: None,
-                        lock_type: None,
-                    });
-                    header.commons.push(common);
-                }
+        ));
+    }
+
+    #[test]
+    fn resolves_bulkhead_controller_doors() {
+        let door = |id| Door {
+            id,
+            idx: 0,
+            serial: 0,
+            checkpoint: false,
+            variant: DoorVariant::BulkheadDoor,
+            size: DoorSize::Large,
+        };
+        let header = Header {
+            doors: vec![door(10), door(11)],
+            ..Default::default()
+        };
+        let controller = BulkheadController {
+            id: 1,
+            idx: 0,
+            serial: 0,
+            main: Some(11),
+            secondary: Some(12),
+            ovl: None,
+        };
+
+        let doors = controller.doors(&header);
+
+        assert_eq!(doors.main.map(|door| door.id), Some(11));
+        assert!(doors.secondary.is_none());
+        assert!(doors.ovl.is_none());
+    }
+
+    fn geometry_header(version: &str, body: &Writer) -> Writer {
+        let mut w = Writer::default();
+        w.string("0.0.1").u16(3);
+        w.u16(0).string("ReplayRecorder.Header").string("0.0.1");
+        w.u16(1)
+            .string("ReplayRecorder.EndOfHeader")
+            .string("0.0.1");
+        w.u16(2).string("Vanilla.Map.Geometry").string(version);
+        w.u16(2).0.extend_from_slice(&body.0);
+        w.u16(1);
+        w
+    }
+
+    #[test]
+    fn decodes_geometry_with_u16_and_u32_counts() {
+        let mut v1 = Writer::default();
+        v1.u8(0).u16(3).u32(3);
+        for _ in 0..3 {
+            v1.f32(0.).f32(0.).f32(0.);
+        }
+        v1.u16(0).u16(1).u16(2);
+        let mut v2 = Writer::default();
+        v2.u8(1).u32(3).u32(3);
+        for _ in 0..3 {
+            v2.f32(0.).f32(0.).f32(0.);
+        }
+        v2.u32(0).u32(1).u32(70_000);
+
+        let mut registry = TypeRegistry::vanilla();
+        registry.register("Vanilla.Map.Geometry", "0.0.9", decode_geometry_u32);
+
+        for (version, body, indices) in [("0.0.1", v1, [0, 1, 2]), ("0.0.9", v2, [0, 1, 70_000])] {
+            let mut w = Writer::default();
+            w.block(&geometry_header(version, &body));
+            let replay = parse_replay_with(&w.0, &registry).unwrap();
+
+            let geometry = &replay.header.level_geometry[0];
+            assert_eq!(geometry.vertices.len(), 3);
+            assert_eq!(geometry.indices, indices);
+        }
+    }
+
+    #[test]
+    fn keeps_geometry_of_unknown_versions_undecoded() {
+        let mut body = Writer::default();
+        body.u8(0).u32(3).u32(3);
+        let mut w = Writer::default();
+        w.block(&geometry_header("0.0.9", &body));
+
+        let replay = parse_replay(&w.0).unwrap();
+
+        assert!(replay.header.level_geometry.is_empty());
+        let block = &replay.header.unknown[0];
+        assert_eq!(
+            (&*block.typename, &*block.version),
+            ("Vanilla.Map.Geometry", "0.0.9")
+        );
+    }
+
+    #[test]
+    fn decodes_player_spawn_stats_and_backpack() {
+        let mut header = Writer::default();
+        header.string("0.0.1").u16(6);
+        for (id, typename, version) in [
+            (0, "ReplayRecorder.Header", "0.0.1"),
+            (1, "ReplayRecorder.EndOfHeader", "0.0.1"),
+            (2, "ReplayRecorder.Spawn", "0.0.1"),
+            (3, "Vanilla.Player", "0.0.2"),
+            (4, "Vanilla.Player.Stats", "0.0.1"),
+            (5, "Vanilla.Player.Backpack", "0.0.1"),
+        ] {
+            header.u16(id).string(typename).string(version);
+        }
+        header.u16(0).string("0.0.1").u8(1);
+        header.u16(1);
+
+        let mut snapshot = Writer::default();
+        snapshot.u32(0).u32(1);
+        snapshot.u16(0).u16(2).u16(3).i32(7).common(0, [0., 0., 0.]);
+        snapshot.u64(76561198000000000).u8(2).string("Dauda");
+        snapshot.u16(2);
+        // Half health, no infection.
+        snapshot.u16(4).u32(1).i32(7).u16(0x3800).u16(0);
+        snapshot.u16(5).u32(1).i32(7);
+        snapshot.u8(1).string("Shelling S49").u16(3);
+        snapshot.u8(2).u8(2).u16(3).u8(3).u16(101);
+        snapshot.u8(1).u8(5).u16(12);
+
+        let mut w = Writer::default();
+        w.block(&header).block(&snapshot);
+        let replay = parse_replay(&w.0).unwrap();
+        let snapshot = &replay.snapshots[0];
+
+        let EventData::Spawn { id, info, .. } = &snapshot.events[0].data else {
+            panic!("expected a spawn");
+        };
+        assert_eq!(*id, 7);
+        assert_eq!(
+            info.as_ref().unwrap(),
+            &SpawnInfo::Player(PlayerInfo {
+                steam_id: 76561198000000000,
+                slot: 2,
+                name: "Dauda".to_string(),
+            })
+        );
+
+        let DynamicData::PlayerStats(stats) = &snapshot.dynamics[0].data else {
+            panic!("expected player stats");
+        };
+        assert_eq!(stats.health, 0.5);
+        assert_eq!(stats.infection, 0.);
+
+        let DynamicData::PlayerBackpack(backpack) = &snapshot.dynamics[1].data else {
+            panic!("expected a player backpack");
+        };
+        assert_eq!(
+            backpack.equipped,
+            IdentifierType::Gear("Shelling S49".into(), 3)
+        );
+        assert_eq!(
+            backpack.slots,
+            [IdentifierType::AliasGear(3), IdentifierType::Item(101)]
+        );
+        assert_eq!(backpack.vanity, [IdentifierType::Vanity(12)]);
+    }
+
+    #[test]
+    fn reads_spawn_info_from_later_versions() {
+        let player_spawn = |version: &str| {
+            let mut header = Writer::default();
+            header.string("0.0.1").u16(4);
+            for (id, typename, version) in [
+                (0, "ReplayRecorder.Header", "0.0.1"),
+                (1, "ReplayRecorder.EndOfHeader", "0.0.1"),
+                (2, "ReplayRecorder.Spawn", "0.0.1"),
+                (3, "Vanilla.Player", version),
+            ] {
+                header.u16(id).string(typename).string(version);
             }
-            DataType {
-                typename: "Vanilla.Map.ResourceContainers", // u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool)
-                version: "0.0.2",
-                ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(
-                    pair(
-                        tuple((le_i32, parse_commons)),
-                        tuple((
-                            le_u16,
-                            parse_replay_bool,
-                            parse_replay_identifier_type,
-                            parse_replay_bool,
-                        )),
-                    ),
-                    n.into(),
-                )(i)?;
-                for ((id, common), (serial, locker, consumable_type, registered)) in items {
-                    header.resource_containers.push(ResourceContainer {
-                        id,
-                        idx: header.commons.len(),
-                        serial,
-                        locker,
-                        consumable_type: Some(consumable_type),
-                        registered: Some(registered),
-                        lock_type: None,
-                    });
-                    header.commons.push(common);
-                }
+            header.u16(0).string("0.0.1").u8(1);
+            header.u16(1);
+
+            let mut snapshot = Writer::default();
+            snapshot.u32(0).u32(1);
+            snapshot.u16(0).u16(2).u16(3).i32(7).common(0, [0., 0., 0.]);
+            snapshot.u64(76561198000000000).u8(0).string("Woods");
+            snapshot.u16(0);
+
+            let mut w = Writer::default();
+            w.block(&header).block(&snapshot);
+            w.0
+        };
+
+        let later = player_spawn("0.1.0");
+        let replay = parse_replay(&later).unwrap();
+        assert!(matches!(
+            replay.snapshots[0].events[0].data,
+            EventData::Spawn { info: Some(_), .. }
+        ));
+
+        // Unknown layout, the snapshot ends at the spawn.
+        let unknown = player_spawn("0.0.2-beta");
+        let replay = parse_replay(&unknown).unwrap();
+        let snapshot = &replay.snapshots[0];
+        assert!(snapshot.events.is_empty());
+        assert_eq!(
+            snapshot.undecoded.as_ref().unwrap().typename,
+            "ReplayRecorder.Spawn"
+        );
+    }
+
+    #[test]
+    fn decodes_enemy_spawn_and_state_events_only_when_asked() {
+        let mut header = Writer::default();
+        header.string("0.0.1").u16(7);
+        for (id, typename, version) in [
+            (0, "ReplayRecorder.Header", "0.0.1"),
+            (1, "ReplayRecorder.EndOfHeader", "0.0.1"),
+            (2, "ReplayRecorder.Spawn", "0.0.1"),
+            (3, "Vanilla.Enemy", "0.0.2"),
+            (4, "Vanilla.Enemy.Health", "0.0.1"),
+            (5, "Vanilla.Enemy.Target", "0.0.1"),
+            (6, "Vanilla.Enemy.Behaviour", "0.0.1"),
+        ] {
+            header.u16(id).string(typename).string(version);
+        }
+        header.u16(0).string("0.0.1").u8(1);
+        header.u16(1);
+
+        let mut snapshot = Writer::default();
+        snapshot.u32(0).u32(5);
+        snapshot.u16(0).u16(2).u16(3).i32(9).common(0, [0., 0., 0.]);
+        snapshot.u8(4).u16(20).f32(60.);
+        snapshot.u16(0).u16(4).i32(9).f32(35.5);
+        snapshot.u16(0).u16(5).i32(9).u8(1).i32(7);
+        snapshot.u16(0).u16(5).i32(9).u8(0).i32(0);
+        snapshot.u16(0).u16(6).i32(9).u8(4);
+        snapshot.u16(0);
+
+        let mut w = Writer::default();
+        w.block(&header).block(&snapshot);
+
+        // The layouts are provisional, left undecoded by default.
+        let replay = parse_replay(&w.0).unwrap();
+        let snapshot = &replay.snapshots[0];
+        assert!(snapshot.events.is_empty());
+        assert_eq!(
+            snapshot.undecoded.as_ref().unwrap().typename,
+            "ReplayRecorder.Spawn"
+        );
+
+        let mut registry = TypeRegistry::vanilla();
+        registry.register_provisional_enemies();
+        let replay = parse_replay_with(&w.0, &registry).unwrap();
+        let events: Vec<_> = replay.snapshots[0].events.iter().map(|e| &e.data).collect();
+
+        let EventData::Spawn { info, .. } = events[0] else {
+            panic!("expected a spawn");
+        };
+        assert_eq!(
+            info.as_ref().unwrap(),
+            &SpawnInfo::Enemy(EnemyInfo {
+                kind: IdentifierType::Enemy(20),
+                max_health: 60.,
+            })
+        );
+        assert!(matches!(
+            events[1],
+            EventData::EnemyHealth { id: 9, health } if *health == 35.5
+        ));
+        assert!(matches!(
+            events[2],
+            EventData::EnemyTarget {
+                id: 9,
+                target: Some(7)
             }
-            DataType {
-                typename: "Vanilla.Map.ResourceContainers", // u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool)
-                version: "0.0.3",
-                ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(
-                    pair(
-                        tuple((le_i32, parse_commons)),
-                        tuple((
-                            le_u16,
-                            parse_replay_bool,
-                            parse_replay_identifier_type,
-                            parse_replay_bool,
-                            le_u8,
-                        )),
-                    ),
-                    n.into(),
-                )(i)?;
-                for ((id, common), (serial, locker, consumable_type, registered, lock_type)) in
-                    items
-                {
-                    header.resource_containers.push(ResourceContainer {
-                        id,
-                        idx: header.commons.len(),
-                        serial,
-                        locker,
-                        consumable_type: Some(consumable_type),
-                        registered: Some(registered),
-                        lock_type: LockType::from_repr(lock_type),
-                    });
-                    header.commons.push(common);
-                }
+        ));
+        assert!(matches!(
+            events[3],
+            EventData::EnemyTarget {
+                id: 9,
+                target: None
             }
-            DataType {
-                typename: "Vanilla.Enemy.Spitters", // u16, (i32, u8, f32 * 3, f16 * 3 + u8, f16)
-                ..
-            } => {
-                let n;
-                let items;
-                (i, n) = le_u16(i)?;
-                // info!("{:?}", n);
-                (i, items) = count(pair(tuple((le_i32, parse_commons)), le_f16), n.into())(i)?;
-                for ((id, common), scale) in items {
-                    header.spitters.push(Spitter {
-                        id,
-                        idx: header.commons.len(),
-                        scale,
-                    });
-                    header.commons.push(common);
-                }
+        ));
+        assert!(matches!(
+            events[4],
+            EventData::EnemyBehaviour {
+                id: 9,
+                behaviour: EnemyBehaviour::Combat
             }
-            _ => {}
-        };
+        ));
     }
-
-    Ok((i, (typemap, header)))
 }
@@ -0,0 +1,126 @@
+//! Conversion of the borrowed replay model into one that owns its data.
+//!
+//! Parsing borrows strings and raw blocks from the input buffer, which is the
+//! cheapest option for tools that keep the buffer around. `into_owned` copies
+//! everything that is still borrowed, so the result can outlive the buffer.
+
+use std::borrow::Cow;
+
+use super::*;
+
+/// Replay that does not borrow from the buffer it was parsed from.
+pub type ReplayOwned = Replay<'static>;
+
+fn owned<T: ToOwned + ?Sized>(v: Cow<'_, T>) -> Cow<'static, T> {
+    Cow::Owned(v.into_owned())
+}
+
+impl Replay<'_> {
+    pub fn into_owned(self) -> ReplayOwned {
+        Replay {
+            typemap: self.typemap.into_owned(),
+            header: self.header.into_owned(),
+            snapshots: self.snapshots,
+        }
+    }
+}
+
+impl Typemap<'_> {
+    pub fn into_owned(self) -> Typemap<'static> {
+        Typemap {
+            version: owned(self.version),
+            entries: self.entries,
+            types: self.types.into_iter().map(DataType::into_owned).collect(),
+        }
+    }
+}
+
+impl DataType<'_> {
+    pub fn into_owned(self) -> DataType<'static> {
+        DataType {
+            id: self.id,
+            typename: owned(self.typename),
+            version: owned(self.version),
+        }
+    }
+}
+
+impl Header<'_> {
+    pub fn into_owned(self) -> Header<'static> {
+        Header {
+            commons: self.commons,
+            replay_header: self.replay_header.map(ReplayHeader::into_owned),
+            metadata: self.metadata.map(Metadata::into_owned),
+            level_geometry: self.level_geometry,
+            doors: self.doors,
+            ladders: self.ladders,
+            terminals: self.terminals,
+            generators: self.generators,
+            resource_containers: self
+                .resource_containers
+                .into_iter()
+                .map(ResourceContainer::into_owned)
+                .collect(),
+            disinfect_stations: self.disinfect_stations,
+            bulkhead_controllers: self.bulkhead_controllers,
+            spitters: self.spitters,
+            unknown: self.unknown.into_iter().map(RawBlock::into_owned).collect(),
+            extensions: self.extensions,
+        }
+    }
+}
+
+impl RawBlock<'_> {
+    pub fn into_owned(self) -> RawBlock<'static> {
+        RawBlock {
+            typename: owned(self.typename),
+            version: owned(self.version),
+            bytes: owned(self.bytes),
+        }
+    }
+}
+
+impl IdentifierType<'_> {
+    pub fn into_owned(self) -> IdentifierType<'static> {
+        match self {
+            IdentifierType::Unknown => IdentifierType::Unknown,
+            IdentifierType::Gear(gear, alias) => IdentifierType::Gear(owned(gear), alias),
+            IdentifierType::AliasGear(id) => IdentifierType::AliasGear(id),
+            IdentifierType::Item(id) => IdentifierType::Item(id),
+            IdentifierType::Enemy(id) => IdentifierType::Enemy(id),
+            IdentifierType::Vanity(id) => IdentifierType::Vanity(id),
+        }
+    }
+}
+
+impl ReplayHeader<'_> {
+    pub fn into_owned(self) -> ReplayHeader<'static> {
+        ReplayHeader {
+            version: owned(self.version),
+            master: self.master,
+        }
+    }
+}
+
+impl Metadata<'_> {
+    pub fn into_owned(self) -> Metadata<'static> {
+        Metadata {
+            version: owned(self.version),
+            compatability_old_dc: self.compatability_old_dc,
+        }
+    }
+}
+
+impl ResourceContainer<'_> {
+    pub fn into_owned(self) -> ResourceContainer<'static> {
+        ResourceContainer {
+            id: self.id,
+            idx: self.idx,
+            serial: self.serial,
+            locker: self.locker,
+            registered: self.registered,
+            consumable_type: self.consumable_type.map(IdentifierType::into_owned),
+            lock_type: self.lock_type,
+        }
+    }
+}
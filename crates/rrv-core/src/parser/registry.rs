@@ -0,0 +1,318 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+};
+
+use nom::{bytes::complete::take, combinator::recognize, number::complete::le_u16};
+
+use super::{vanilla, DataType, DynamicData, EventData, Header, PResult, RawBlock, Typemap};
+
+/// Decoder for the payload of one header block.
+///
+/// Implemented for plain functions with the matching signature, so most
+/// handlers can be registered without a wrapper type.
+pub trait TypeHandler: Send + Sync {
+    /// Decodes one payload of `t` from `i` into `header`, returning the rest of the input.
+    fn decode<'a>(&self, i: &'a [u8], t: &DataType<'a>, header: &mut Header<'a>)
+        -> PResult<'a, ()>;
+}
+
+impl<F> TypeHandler for F
+where
+    F: for<'a> Fn(&'a [u8], &DataType<'a>, &mut Header<'a>) -> PResult<'a, ()> + Send + Sync,
+{
+    fn decode<'a>(
+        &self,
+        i: &'a [u8],
+        t: &DataType<'a>,
+        header: &mut Header<'a>,
+    ) -> PResult<'a, ()> {
+        self(i, t, header)
+    }
+}
+
+/// Layout of a header block payload, used to step over types that have no
+/// decoder without losing alignment. Captured payloads end up in [`Header::unknown`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockSize {
+    /// Payload of a fixed number of bytes.
+    Fixed(usize),
+    /// u16 count followed by that many records of a fixed number of bytes.
+    Counted(usize),
+}
+
+impl BlockSize {
+    /// Takes one payload of this size, returning its raw bytes.
+    pub fn take<'a>(&self, i: &'a [u8]) -> PResult<'a, &'a [u8]> {
+        match *self {
+            Self::Fixed(n) => take(n)(i),
+            Self::Counted(n) => recognize(|i| {
+                let (i, count) = le_u16(i)?;
+                take(usize::from(count).saturating_mul(n))(i)
+            })(i),
+        }
+    }
+}
+
+impl TypeHandler for BlockSize {
+    fn decode<'a>(
+        &self,
+        i: &'a [u8],
+        t: &DataType<'a>,
+        header: &mut Header<'a>,
+    ) -> PResult<'a, ()> {
+        let (i, bytes) = self.take(i)?;
+        header.unknown.push(RawBlock {
+            typename: t.typename.clone(),
+            version: t.version.clone(),
+            bytes: bytes.into(),
+        });
+
+        Ok((i, ()))
+    }
+}
+
+/// Decoder for the payload of one snapshot event. Returning
+/// [`ErrorKind::UnhandledType`](super::ErrorKind::UnhandledType) ends the
+/// decoding of the snapshot like a missing handler.
+pub trait EventHandler: Send + Sync {
+    /// Decodes one event of type `t` from `i`. Type ids in the payload, like
+    /// the spawned type of a spawn, are resolved through `typemap`.
+    fn decode<'a, 't>(
+        &self,
+        i: &'a [u8],
+        t: &DataType<'t>,
+        typemap: &Typemap<'t>,
+    ) -> PResult<'a, EventData>;
+}
+
+impl<F> EventHandler for F
+where
+    F: for<'a, 't> Fn(&'a [u8], &DataType<'t>, &Typemap<'t>) -> PResult<'a, EventData>
+        + Send
+        + Sync,
+{
+    fn decode<'a, 't>(
+        &self,
+        i: &'a [u8],
+        t: &DataType<'t>,
+        typemap: &Typemap<'t>,
+    ) -> PResult<'a, EventData> {
+        self(i, t, typemap)
+    }
+}
+
+/// Decoder for the data of one entity in a snapshot dynamics collection.
+/// Returning [`ErrorKind::UnhandledType`](super::ErrorKind::UnhandledType)
+/// ends the decoding of the snapshot like a missing handler.
+pub trait DynamicHandler: Send + Sync {
+    /// Decodes the data of one entity of type `t` from `i`, after its id.
+    fn decode<'a>(&self, i: &'a [u8], t: &DataType<'_>) -> PResult<'a, DynamicData>;
+}
+
+impl<F> DynamicHandler for F
+where
+    F: for<'a, 't> Fn(&'a [u8], &DataType<'t>) -> PResult<'a, DynamicData> + Send + Sync,
+{
+    fn decode<'a>(&self, i: &'a [u8], t: &DataType<'_>) -> PResult<'a, DynamicData> {
+        self(i, t)
+    }
+}
+
+struct Handlers<H: ?Sized> {
+    versions: HashMap<String, Box<H>>,
+    any: Option<Box<H>>,
+}
+
+impl<H: ?Sized> Default for Handlers<H> {
+    fn default() -> Self {
+        Self {
+            versions: HashMap::new(),
+            any: None,
+        }
+    }
+}
+
+impl<H: ?Sized> Handlers<H> {
+    fn get(&self, version: &str) -> Option<&H> {
+        self.versions
+            .get(version)
+            .or(self.any.as_ref())
+            .map(|handler| handler.as_ref())
+    }
+}
+
+/// Header, event and dynamic type handlers keyed by `typename` and `version`.
+///
+/// Header blocks are not size-prefixed, so the first header type without a
+/// handler ends the decoding of the header, see [`Header::unknown`]. Events
+/// and dynamics without a handler end the decoding of their snapshot, see
+/// [`Snapshot::undecoded`](super::Snapshot::undecoded).
+#[derive(Default)]
+pub struct TypeRegistry {
+    handlers: HashMap<String, Handlers<dyn TypeHandler>>,
+    events: HashMap<String, Handlers<dyn EventHandler>>,
+    dynamics: HashMap<String, Handlers<dyn DynamicHandler>>,
+}
+
+impl TypeRegistry {
+    /// Creates a registry without any handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with handlers for all types written by the vanilla recorder.
+    pub fn vanilla() -> Self {
+        let mut registry = Self::new();
+        vanilla::register(&mut registry);
+        registry
+    }
+
+    /// Adds decoders for enemy spawn info and the `Vanilla.Enemy.Health`,
+    /// `Target` and `Behaviour` events. Their layouts have not been checked
+    /// against the recorder, and a wrong guess fails the whole replay, so
+    /// [`TypeRegistry::vanilla`] leaves them out and snapshots with them end
+    /// up [`undecoded`](super::Snapshot::undecoded).
+    pub fn register_provisional_enemies(&mut self) -> &mut Self {
+        vanilla::register_provisional_enemies(self);
+        self
+    }
+
+    /// Registers a handler for one version of a type, replacing any previous one.
+    pub fn register(
+        &mut self,
+        typename: impl Into<String>,
+        version: impl Into<String>,
+        handler: impl TypeHandler + 'static,
+    ) -> &mut Self {
+        self.handlers
+            .entry(typename.into())
+            .or_default()
+            .versions
+            .insert(version.into(), Box::new(handler));
+        self
+    }
+
+    /// Registers a handler for versions of a type without a handler of their own.
+    pub fn register_any(
+        &mut self,
+        typename: impl Into<String>,
+        handler: impl TypeHandler + 'static,
+    ) -> &mut Self {
+        self.handlers.entry(typename.into()).or_default().any = Some(Box::new(handler));
+        self
+    }
+
+    pub fn get(&self, typename: &str, version: &str) -> Option<&dyn TypeHandler> {
+        self.handlers.get(typename)?.get(version)
+    }
+
+    /// Registers an event handler for one version of a type, replacing any previous one.
+    pub fn register_event(
+        &mut self,
+        typename: impl Into<String>,
+        version: impl Into<String>,
+        handler: impl EventHandler + 'static,
+    ) -> &mut Self {
+        self.events
+            .entry(typename.into())
+            .or_default()
+            .versions
+            .insert(version.into(), Box::new(handler));
+        self
+    }
+
+    /// Registers an event handler for versions of a type without a handler of their own.
+    pub fn register_event_any(
+        &mut self,
+        typename: impl Into<String>,
+        handler: impl EventHandler + 'static,
+    ) -> &mut Self {
+        self.events.entry(typename.into()).or_default().any = Some(Box::new(handler));
+        self
+    }
+
+    pub fn event(&self, typename: &str, version: &str) -> Option<&dyn EventHandler> {
+        self.events.get(typename)?.get(version)
+    }
+
+    /// Registers a dynamic handler for one version of a type, replacing any previous one.
+    pub fn register_dynamic(
+        &mut self,
+        typename: impl Into<String>,
+        version: impl Into<String>,
+        handler: impl DynamicHandler + 'static,
+    ) -> &mut Self {
+        self.dynamics
+            .entry(typename.into())
+            .or_default()
+            .versions
+            .insert(version.into(), Box::new(handler));
+        self
+    }
+
+    /// Registers a dynamic handler for versions of a type without a handler of their own.
+    pub fn register_dynamic_any(
+        &mut self,
+        typename: impl Into<String>,
+        handler: impl DynamicHandler + 'static,
+    ) -> &mut Self {
+        self.dynamics.entry(typename.into()).or_default().any = Some(Box::new(handler));
+        self
+    }
+
+    pub fn dynamic(&self, typename: &str, version: &str) -> Option<&dyn DynamicHandler> {
+        self.dynamics.get(typename)?.get(version)
+    }
+}
+
+impl fmt::Debug for TypeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeRegistry")
+            .field("header", &self.handlers.keys())
+            .field("events", &self.events.keys())
+            .field("dynamics", &self.dynamics.keys())
+            .finish()
+    }
+}
+
+/// Data decoded by handlers registered outside of this crate, one value per type.
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Inserts `value`, returning the previous value of the same type.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|prev| prev.downcast().ok().map(|prev| *prev))
+    }
+
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.map.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    /// Returns the value of type `T`, inserting its default first if missing.
+    pub fn get_or_default<T: Any + Send + Sync + Default>(&mut self) -> &mut T {
+        self.map
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::<T>::default())
+            .downcast_mut()
+            .expect("extension stored under the TypeId of another type")
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use nom::{bytes::streaming::take, number::streaming::le_u32};
+
+use super::{
+    parse_snapshot_body, parse_typemap_and_header, vanilla_registry, Header, PResult, ParseError,
+    ReplayError, Snapshot, TypeRegistry, Typemap,
+};
+
+/// Size-prefixed frame, as written for the header and for each snapshot.
+/// Returns [`nom::Err::Incomplete`] until all bytes of the frame are available.
+pub fn parse_frame(i: &[u8]) -> PResult<'_, &[u8]> {
+    let (i, size) = le_u32(i)?;
+    take(size)(i)
+}
+
+/// Incremental parser for a replay that is still being recorded.
+///
+/// Chunks are added with [`push`](Self::push) as the recorder writes them, and
+/// each snapshot is returned by [`next_snapshot`](Self::next_snapshot) as soon
+/// as all of its bytes have arrived.
+#[derive(Debug, Default)]
+pub struct ReplayStream {
+    buf: Vec<u8>,
+    /// Decodes the header and snapshots, the vanilla registry if `None`.
+    registry: Option<Arc<TypeRegistry>>,
+    /// Length of the header frame at the start of `buf`, once complete.
+    header_len: Option<usize>,
+    typemap: Option<Typemap<'static>>,
+    header: Option<Header<'static>>,
+    /// Error of the header, which no snapshot can be decoded without.
+    header_error: Option<ReplayError>,
+    /// Offset in `buf` of the next unread snapshot frame.
+    pos: usize,
+    /// Bytes of returned snapshots that were dropped from `buf`.
+    dropped: usize,
+}
+
+impl ReplayStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a stream decoding header types, events and dynamics through `registry`.
+    pub fn with_registry(registry: Arc<TypeRegistry>) -> Self {
+        Self {
+            registry: Some(registry),
+            ..Self::default()
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) {
+        // Only read snapshot frames are dropped, offsets of errors count from
+        // the start of the header.
+        if let Some(header_len) = self.header_len {
+            self.dropped += self.pos - header_len;
+            self.buf.drain(header_len..self.pos);
+            self.pos = header_len;
+        }
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Total number of bytes pushed so far.
+    pub fn len(&self) -> usize {
+        self.dropped + self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the header, or `None` while it is incomplete. It is parsed once,
+    /// as soon as all of its bytes have arrived.
+    pub fn header(&mut self) -> Result<Option<(&Typemap<'static>, &Header<'static>)>, ReplayError> {
+        if !self.read_header()? {
+            return Ok(None);
+        }
+        Ok(self.typemap.as_ref().zip(self.header.as_ref()))
+    }
+
+    /// Returns the next snapshot, or `None` until more of it is pushed.
+    ///
+    /// A malformed snapshot is returned as an error once and then skipped.
+    /// After an error in the header, no snapshots are returned.
+    pub fn next_snapshot(&mut self) -> Result<Option<Snapshot>, ReplayError> {
+        if self.header_error.is_some() || !self.read_header()? {
+            return Ok(None);
+        }
+
+        let typemap = self
+            .typemap
+            .as_ref()
+            .expect("typemap is parsed with the header");
+        match parse_frame(&self.buf[self.pos..]) {
+            Ok((rest, bytes)) => {
+                let end = self.buf.len() - rest.len();
+                let snapshot = parse_snapshot_body(bytes, typemap, self.registry())
+                    .map(|(_, snapshot)| Some(snapshot))
+                    .map_err(|e| self.error(e));
+                self.pos = end;
+                snapshot
+            }
+            Err(nom::Err::Incomplete(_)) => Ok(None),
+            Err(e) => Err(self.error(e)),
+        }
+    }
+
+    fn registry(&self) -> &TypeRegistry {
+        match &self.registry {
+            Some(registry) => registry,
+            None => vanilla_registry(),
+        }
+    }
+
+    /// Parses the header once its frame is complete, returning whether it is.
+    fn read_header(&mut self) -> Result<bool, ReplayError> {
+        if self.header.is_some() {
+            return Ok(true);
+        }
+        if let Some(e) = &self.header_error {
+            return Err(e.clone());
+        }
+
+        let parsed = match parse_frame(&self.buf) {
+            Ok((rest, bytes)) => parse_typemap_and_header(bytes, self.registry())
+                .map(|(_, (typemap, header))| {
+                    let header_len = self.buf.len() - rest.len();
+                    Some((header_len, typemap.into_owned(), header.into_owned()))
+                })
+                .map_err(|e| self.error(e)),
+            Err(nom::Err::Incomplete(_)) => Ok(None),
+            Err(e) => Err(self.error(e)),
+        };
+        match parsed {
+            Ok(Some((header_len, typemap, header))) => {
+                self.header_len = Some(header_len);
+                self.pos = header_len;
+                self.typemap = Some(typemap);
+                self.header = Some(header);
+                Ok(true)
+            }
+            Ok(None) => Ok(false),
+            Err(e) => {
+                self.header_error = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn error(&self, e: nom::Err<ParseError>) -> ReplayError {
+        let error = ReplayError::from_nom(&self.buf, e);
+        match self.header_len {
+            Some(header_len) if error.offset() >= header_len => error.offset_by(self.dropped),
+            _ => error,
+        }
+    }
+}
+
+impl Iterator for ReplayStream {
+    type Item = Result<Snapshot, ReplayError>;
+
+    /// Yields complete snapshots, ending whenever the stream runs out of data.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_snapshot().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::tests::{header_block, replay_bytes, snapshot_block, Writer};
+
+    #[test]
+    fn yields_snapshots_as_chunks_arrive() {
+        let bytes = replay_bytes();
+        let mut stream = ReplayStream::new();
+        let mut timestamps = vec![];
+
+        for chunk in bytes.chunks(7) {
+            stream.push(chunk);
+            while let Some(snapshot) = stream.next_snapshot().unwrap() {
+                timestamps.push(snapshot.timestamp);
+            }
+        }
+
+        assert_eq!(timestamps, [0, 100]);
+        let (_, header) = stream.header().unwrap().unwrap();
+        assert_eq!(header.ladders.len(), 1);
+        assert_eq!(stream.len(), bytes.len());
+    }
+
+    #[test]
+    fn waits_for_incomplete_snapshot() {
+        let bytes = replay_bytes();
+        let mut stream = ReplayStream::new();
+
+        stream.push(&bytes[..bytes.len() - 1]);
+        assert_eq!(stream.by_ref().count(), 1);
+        assert!(stream.next_snapshot().unwrap().is_none());
+
+        stream.push(&bytes[bytes.len() - 1..]);
+        assert_eq!(stream.next_snapshot().unwrap().unwrap().timestamp, 100);
+    }
+
+    #[test]
+    fn reports_offset_from_start_of_stream() {
+        let mut stream = ReplayStream::new();
+        let bytes = replay_bytes();
+        stream.push(&bytes);
+        assert_eq!(stream.by_ref().count(), 2);
+
+        let mut bad = snapshot_block(200, 3.);
+        bad.0[10..12].copy_from_slice(&9u16.to_le_bytes());
+        let mut w = Writer::default();
+        w.block(&bad);
+        stream.push(&w.0);
+
+        assert!(matches!(
+            stream.next_snapshot(),
+            Err(ReplayError::Malformed { offset, .. }) if offset == bytes.len() + 4 + 8
+        ));
+    }
+
+    #[test]
+    fn skips_malformed_snapshot() {
+        let mut bad = snapshot_block(0, 1.);
+        bad.0[10..12].copy_from_slice(&9u16.to_le_bytes());
+        let mut w = Writer::default();
+        w.block(&header_block())
+            .block(&bad)
+            .block(&snapshot_block(100, 2.));
+        let mut stream = ReplayStream::new();
+        stream.push(&w.0);
+
+        let snapshots: Vec<_> = stream.by_ref().collect();
+
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots[0].is_err());
+        assert_eq!(snapshots[1].as_ref().unwrap().timestamp, 100);
+        assert!(stream.next_snapshot().unwrap().is_none());
+    }
+
+    #[test]
+    fn stops_after_malformed_header() {
+        let mut header = header_block();
+        header.0.truncate(header.0.len() - 2);
+        header.u16(99);
+        let mut w = Writer::default();
+        w.block(&header).block(&snapshot_block(0, 1.));
+        let mut stream = ReplayStream::new();
+        stream.push(&w.0);
+
+        assert!(stream.header().is_err());
+        assert_eq!(stream.count(), 0);
+    }
+}
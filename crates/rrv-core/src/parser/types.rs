@@ -1,6 +1,10 @@
+use std::{any::Any, borrow::Cow};
+
+use super::{Common, DoorState, EnemyBehaviour, IdentifierType, RawBlock};
+
 #[derive(Debug)]
 pub struct Typemap<'a> {
-    pub version: &'a str,
+    pub version: Cow<'a, str>,
     pub entries: u16,
     pub types: Vec<DataType<'a>>,
 }
@@ -8,18 +12,119 @@ pub struct Typemap<'a> {
 #[derive(Debug)]
 pub struct DataType<'a> {
     pub id: u16,
-    pub typename: &'a str,
-    pub version: &'a str,
+    pub typename: Cow<'a, str>,
+    pub version: Cow<'a, str>,
+}
+
+impl<'a> Typemap<'a> {
+    pub fn get(&self, id: u16) -> Option<&DataType<'a>> {
+        self.types.iter().find(|&v| v.id == id)
+    }
 }
 
+#[derive(Debug)]
 pub struct Snapshot {
-    timestamp: u32,
+    /// Milliseconds since the start of the recording.
+    pub timestamp: u32,
+    pub events: Vec<Event>,
+    pub dynamics: Vec<Dynamic>,
+    /// Rest of the snapshot from the first event or dynamics collection
+    /// without a handler, starting after its type id. Events and dynamics
+    /// are not size-prefixed, so nothing after it can be decoded.
+    pub undecoded: Option<RawBlock<'static>>,
 }
 
+#[derive(Debug)]
 pub struct Event {
-    id: u16,
-    offset: u16,
-    // ...
+    /// Type id of the event in the [`Typemap`].
+    pub id: u16,
+    /// Milliseconds after the timestamp of the owning [`Snapshot`].
+    pub offset: u16,
+    pub data: EventData,
 }
 
-pub struct Dynamic {}
+#[derive(Debug)]
+pub enum EventData {
+    Spawn {
+        type_id: u16,
+        id: i32,
+        common: Common,
+        /// Recorded for players from `Vanilla.Player` 0.0.2 on, and for enemies
+        /// from `Vanilla.Enemy` 0.0.2 on.
+        info: Option<SpawnInfo>,
+    },
+    Despawn {
+        type_id: u16,
+        id: i32,
+    },
+    DoorStatus {
+        id: i32,
+        state: DoorState,
+    },
+    EnemyHealth {
+        id: i32,
+        health: f32,
+    },
+    EnemyTarget {
+        id: i32,
+        /// Id of the player the enemy is after.
+        target: Option<i32>,
+    },
+    EnemyBehaviour {
+        id: i32,
+        behaviour: EnemyBehaviour,
+    },
+    /// Decoded by an [`EventHandler`](super::EventHandler) registered outside of this crate.
+    Extension(Box<dyn Any + Send + Sync>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpawnInfo {
+    Player(PlayerInfo),
+    Enemy(EnemyInfo),
+}
+
+#[derive(Debug)]
+pub struct Dynamic {
+    /// Type id of the dynamic in the [`Typemap`].
+    pub type_id: u16,
+    pub id: i32,
+    pub data: DynamicData,
+}
+
+#[derive(Debug)]
+pub enum DynamicData {
+    Transform(Common),
+    PlayerStats(PlayerStats),
+    PlayerBackpack(PlayerBackpack),
+    /// Decoded by a [`DynamicHandler`](super::DynamicHandler) registered outside of this crate.
+    Extension(Box<dyn Any + Send + Sync>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerInfo {
+    pub steam_id: u64,
+    /// Lobby slot, from 0 to 3.
+    pub slot: u8,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemyInfo {
+    pub kind: IdentifierType<'static>,
+    pub max_health: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerStats {
+    pub health: f16,
+    pub infection: f16,
+}
+
+/// Gear carried by a player, and the vanity items they wear.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlayerBackpack {
+    pub equipped: IdentifierType<'static>,
+    pub slots: Vec<IdentifierType<'static>>,
+    pub vanity: Vec<IdentifierType<'static>>,
+}
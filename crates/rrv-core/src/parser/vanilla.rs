@@ -0,0 +1,642 @@
+//! Decoders for the header, event and dynamic types written by the vanilla recorder.
+
+use nom::{
+    combinator::map,
+    multi::count,
+    number::complete::{le_f32, le_i32, le_u16, le_u32, le_u64, le_u8},
+    sequence::{pair, tuple},
+};
+
+use super::*;
+
+pub(super) fn register(registry: &mut TypeRegistry) {
+    registry.register_any("ReplayRecorder.Header", decode_replay_header);
+    registry.register("Vanilla.Metadata", "0.0.1", decode_metadata_v1);
+    registry.register("Vanilla.Metadata", "0.0.2", decode_metadata_v2);
+    registry.register("Vanilla.Map.Geometry", "0.0.1", decode_geometry);
+    registry.register_any("Vanilla.Map.Geometry.EOH", decode_geometry_eoh);
+    registry.register_any("Vanilla.Map.Doors", decode_doors);
+    registry.register_any("Vanilla.Map.Ladders", decode_ladders);
+    registry.register_any("Vanilla.Map.Terminals", decode_terminals);
+    registry.register_any("Vanilla.Map.Generators", decode_generators);
+    registry.register_any("Vanilla.Map.DisinfectStations", decode_disinfect_stations);
+    registry.register_any(
+        "Vanilla.Map.BulkheadControllers",
+        decode_bulkhead_controllers,
+    );
+    registry.register(
+        "Vanilla.Map.ResourceContainers",
+        "0.0.1",
+        decode_resource_containers_v1,
+    );
+    registry.register(
+        "Vanilla.Map.ResourceContainers",
+        "0.0.2",
+        decode_resource_containers_v2,
+    );
+    registry.register(
+        "Vanilla.Map.ResourceContainers",
+        "0.0.3",
+        decode_resource_containers_v3,
+    );
+    registry.register_any("Vanilla.Enemy.Spitters", decode_spitters);
+
+    registry.register_event_any("ReplayRecorder.Spawn", decode_spawn);
+    registry.register_event_any("ReplayRecorder.Despawn", decode_despawn);
+    registry.register_event_any("Vanilla.Map.DoorStatusChange", decode_door_status);
+
+    registry.register_dynamic_any("Vanilla.Player", decode_transform);
+    registry.register_dynamic_any("Vanilla.Enemy", decode_transform);
+    registry.register_dynamic_any("Vanilla.Player.Stats", decode_player_stats);
+    registry.register_dynamic_any("Vanilla.Player.Backpack", decode_player_backpack);
+}
+
+/// Enemy spawn info and the `Vanilla.Enemy.*` events, whose layouts have not
+/// been checked against the recorder's `Vanilla.Enemy` writer yet.
+pub(super) fn register_provisional_enemies(registry: &mut TypeRegistry) {
+    registry.register_event_any("ReplayRecorder.Spawn", decode_spawn_provisional);
+    registry.register_event_any("Vanilla.Enemy.Health", decode_enemy_health);
+    registry.register_event_any("Vanilla.Enemy.Target", decode_enemy_target);
+    registry.register_event_any("Vanilla.Enemy.Behaviour", decode_enemy_behaviour);
+}
+
+// string, bool
+fn decode_replay_header<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let version;
+    let master;
+    (i, (version, master)) = pair(parse_replay_string, parse_replay_bool)(i)?;
+    header.replay_header = Some(ReplayHeader {
+        version: version.into(),
+        master,
+    });
+
+    Ok((i, ()))
+}
+
+// string
+fn decode_metadata_v1<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let version;
+    (i, version) = parse_replay_string(i)?;
+    header.metadata = Some(Metadata {
+        version: version.into(),
+        compatability_old_dc: None,
+    });
+
+    Ok((i, ()))
+}
+
+// string, bool
+fn decode_metadata_v2<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let version;
+    let compat;
+    (i, (version, compat)) = pair(parse_replay_string, parse_replay_bool)(i)?;
+    header.metadata = Some(Metadata {
+        version: version.into(),
+        compatability_old_dc: Some(compat),
+    });
+
+    Ok((i, ()))
+}
+
+// u8, u16, u32, f32 * 3 list, u16 list
+fn decode_geometry<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let dimension;
+    let num_vert;
+    let num_idx;
+    let vertices;
+    let indices;
+    (i, (dimension, num_vert, num_idx)) = tuple((le_u8, le_u16, le_u32))(i)?;
+    (i, vertices) = count(parse_vec3, num_vert.into())(i)?;
+    let Ok(num_idx) = usize::try_from(num_idx) else {
+        return ParseError::err(i, ErrorKind::InvalidLength(num_idx));
+    };
+    (i, indices) = count(map(le_u16, u32::from), num_idx)(i)?;
+    header.level_geometry.push(Geometry {
+        dimension,
+        vertices,
+        indices,
+    });
+
+    Ok((i, ()))
+}
+
+/// Decoder for `Vanilla.Map.Geometry` blocks with u32 vertex counts and
+/// indices, for surfaces with more than `u16::MAX` vertices.
+///
+/// No released recorder writes this layout yet, so [`TypeRegistry::vanilla`]
+/// does not register it. Register it for the version of a recorder that does.
+// u8, u32, u32, f32 * 3 list, u32 list
+pub fn decode_geometry_u32<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let dimension;
+    let num_vert;
+    let num_idx;
+    let vertices;
+    let indices;
+    (i, (dimension, num_vert, num_idx)) = tuple((le_u8, le_u32, le_u32))(i)?;
+    let Ok(num_vert) = usize::try_from(num_vert) else {
+        return ParseError::err(i, ErrorKind::InvalidLength(num_vert));
+    };
+    (i, vertices) = count(parse_vec3, num_vert)(i)?;
+    let Ok(num_idx) = usize::try_from(num_idx) else {
+        return ParseError::err(i, ErrorKind::InvalidLength(num_idx));
+    };
+    (i, indices) = count(le_u32, num_idx)(i)?;
+    header.level_geometry.push(Geometry {
+        dimension,
+        vertices,
+        indices,
+    });
+
+    Ok((i, ()))
+}
+
+fn decode_geometry_eoh<'a>(i: &'a [u8], _: &DataType<'a>, _: &mut Header<'a>) -> PResult<'a, ()> {
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool, u8, u8)
+fn decode_doors<'a>(mut i: &'a [u8], _: &DataType<'a>, header: &mut Header<'a>) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(
+        pair(
+            tuple((le_i32, parse_commons)),
+            tuple((le_u16, parse_replay_bool, le_u8, le_u8)),
+        ),
+        n.into(),
+    )(i)?;
+    for ((id, common), (serial, checkpoint, variant, size)) in items {
+        header.doors.push(Door {
+            id,
+            idx: header.commons.len(),
+            serial,
+            checkpoint,
+            variant: DoorVariant::from_repr(variant).unwrap_or_default(),
+            size: DoorSize::from_repr(size).unwrap_or_default(),
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (u8, f32 * 3, f16 * 3 + u8, f16)
+fn decode_ladders<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(pair(parse_commons, le_f16), n.into())(i)?;
+    for (common, height) in items {
+        header.ladders.push(Ladder {
+            idx: header.commons.len(),
+            height,
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8)
+fn decode_terminals<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(tuple((le_i32, parse_commons)), n.into())(i)?;
+    for (id, common) in items {
+        header.terminals.push(Terminal {
+            id,
+            idx: header.commons.len(),
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16)
+fn decode_generators<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(pair(tuple((le_i32, parse_commons)), le_u16), n.into())(i)?;
+    for ((id, common), serial) in items {
+        header.generators.push(Generator {
+            id,
+            idx: header.commons.len(),
+            serial,
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16)
+fn decode_disinfect_stations<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(pair(tuple((le_i32, parse_commons)), le_u16), n.into())(i)?;
+    for ((id, common), serial) in items {
+        header.disinfect_stations.push(DisinfectStation {
+            id,
+            idx: header.commons.len(),
+            serial,
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool, i32?, bool, i32?, bool, i32?)
+fn decode_bulkhead_controllers<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(
+        pair(
+            tuple((le_i32, parse_commons)),
+            tuple((le_u16, parse_bulkhead_dc)),
+        ),
+        n.into(),
+    )(i)?;
+    for ((id, common), (serial, (main, secondary, ovl))) in items {
+        header.bulkhead_controllers.push(BulkheadController {
+            id,
+            idx: header.commons.len(),
+            serial,
+            main,
+            secondary,
+            ovl,
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool)
+fn decode_resource_containers_v1<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(
+        pair(
+            tuple((le_i32, parse_commons)),
+            tuple((le_u16, parse_replay_bool)),
+        ),
+        n.into(),
+    )(i)?;
+    for ((id, common), (serial, locker)) in items {
+        header.resource_containers.push(ResourceContainer {
+            id,
+            idx: header.commons.len(),
+            serial,
+            locker,
+            consumable_type: None,
+            registered: None,
+            lock_type: None,
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool)
+fn decode_resource_containers_v2<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(
+        pair(
+            tuple((le_i32, parse_commons)),
+            tuple((
+                le_u16,
+                parse_replay_bool,
+                parse_replay_identifier_type,
+                parse_replay_bool,
+            )),
+        ),
+        n.into(),
+    )(i)?;
+    for ((id, common), (serial, locker, consumable_type, registered)) in items {
+        header.resource_containers.push(ResourceContainer {
+            id,
+            idx: header.commons.len(),
+            serial,
+            locker,
+            consumable_type: Some(consumable_type),
+            registered: Some(registered),
+            lock_type: None,
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8, u16, bool)
+fn decode_resource_containers_v3<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(
+        pair(
+            tuple((le_i32, parse_commons)),
+            tuple((
+                le_u16,
+                parse_replay_bool,
+                parse_replay_identifier_type,
+                parse_replay_bool,
+                le_u8,
+            )),
+        ),
+        n.into(),
+    )(i)?;
+    for ((id, common), (serial, locker, consumable_type, registered, lock_type)) in items {
+        header.resource_containers.push(ResourceContainer {
+            id,
+            idx: header.commons.len(),
+            serial,
+            locker,
+            consumable_type: Some(consumable_type),
+            registered: Some(registered),
+            lock_type: LockType::from_repr(lock_type),
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+// u16, (i32, u8, f32 * 3, f16 * 3 + u8, f16)
+fn decode_spitters<'a>(
+    mut i: &'a [u8],
+    _: &DataType<'a>,
+    header: &mut Header<'a>,
+) -> PResult<'a, ()> {
+    let n;
+    let items;
+    (i, n) = le_u16(i)?;
+    (i, items) = count(pair(tuple((le_i32, parse_commons)), le_f16), n.into())(i)?;
+    for ((id, common), scale) in items {
+        header.spitters.push(Spitter {
+            id,
+            idx: header.commons.len(),
+            scale,
+        });
+        header.commons.push(common);
+    }
+
+    Ok((i, ()))
+}
+
+/// Numbers of a dotted version, `[0, 0, 2]` for `0.0.2`.
+fn version_numbers(version: &str) -> Option<Vec<u32>> {
+    version.split('.').map(|n| n.parse().ok()).collect()
+}
+
+/// Whether spawns of `t` carry a [`SpawnInfo`] after their transform, which
+/// players and enemies do from 0.0.2 on. `None` for versions that are not
+/// dotted numbers, as the layout of those is unknown and the snapshot is left
+/// undecoded.
+fn has_spawn_info(t: &DataType) -> Option<bool> {
+    Some(version_numbers(&t.version)?[..] >= [0, 0, 2][..])
+}
+
+/// Spawns, leaving snapshots with enemy spawn info undecoded.
+fn decode_spawn<'a, 't>(
+    i: &'a [u8],
+    _: &DataType<'t>,
+    typemap: &Typemap<'t>,
+) -> PResult<'a, EventData> {
+    spawn(i, typemap, false)
+}
+
+/// Spawns, reading enemy spawn info with its provisional layout.
+fn decode_spawn_provisional<'a, 't>(
+    i: &'a [u8],
+    _: &DataType<'t>,
+    typemap: &Typemap<'t>,
+) -> PResult<'a, EventData> {
+    spawn(i, typemap, true)
+}
+
+// u16, i32, (u8, f32 * 3, f16 * 3 + u8), then
+// for players u64, u8, string
+// for enemies identifier, f32, provisional
+fn spawn<'a>(mut i: &'a [u8], typemap: &Typemap, enemy_info: bool) -> PResult<'a, EventData> {
+    let type_id;
+    let id;
+    let common;
+    (i, (type_id, id, common)) = tuple((le_u16, le_i32, parse_commons))(i)?;
+    let spawned = typemap
+        .get(type_id)
+        .filter(|t| t.typename == "Vanilla.Player" || t.typename == "Vanilla.Enemy");
+    let with_info = match spawned.map(|t| (t, has_spawn_info(t))) {
+        Some((t, Some(true))) => Some(t),
+        Some((_, None)) => return ParseError::err(i, ErrorKind::UnhandledType),
+        _ => None,
+    };
+    let mut info = None;
+    if with_info.is_some_and(|t| t.typename == "Vanilla.Player") {
+        let steam_id;
+        let slot;
+        let name;
+        (i, (steam_id, slot, name)) = tuple((le_u64, le_u8, parse_replay_string))(i)?;
+        info = Some(SpawnInfo::Player(PlayerInfo {
+            steam_id,
+            slot,
+            name: name.to_string(),
+        }));
+    } else if with_info.is_some() {
+        if !enemy_info {
+            return ParseError::err(i, ErrorKind::UnhandledType);
+        }
+        let kind;
+        let max_health;
+        (i, (kind, max_health)) = pair(parse_replay_identifier_type, le_f32)(i)?;
+        info = Some(SpawnInfo::Enemy(EnemyInfo {
+            kind: kind.into_owned(),
+            max_health,
+        }));
+    }
+
+    Ok((
+        i,
+        EventData::Spawn {
+            type_id,
+            id,
+            common,
+            info,
+        },
+    ))
+}
+
+// u16, i32
+fn decode_despawn<'a, 't>(
+    i: &'a [u8],
+    _: &DataType<'t>,
+    _: &Typemap<'t>,
+) -> PResult<'a, EventData> {
+    let (i, (type_id, id)) = pair(le_u16, le_i32)(i)?;
+
+    Ok((i, EventData::Despawn { type_id, id }))
+}
+
+// i32, u8
+fn decode_door_status<'a, 't>(
+    i: &'a [u8],
+    _: &DataType<'t>,
+    _: &Typemap<'t>,
+) -> PResult<'a, EventData> {
+    let (i, (id, state)) = pair(le_i32, le_u8)(i)?;
+
+    Ok((
+        i,
+        EventData::DoorStatus {
+            id,
+            state: DoorState::from_repr(state).unwrap_or_default(),
+        },
+    ))
+}
+
+// The `Vanilla.Enemy.*` event layouts below are provisional, see
+// `register_provisional_enemies`.
+
+// i32, f32
+fn decode_enemy_health<'a, 't>(
+    i: &'a [u8],
+    _: &DataType<'t>,
+    _: &Typemap<'t>,
+) -> PResult<'a, EventData> {
+    let (i, (id, health)) = pair(le_i32, le_f32)(i)?;
+
+    Ok((i, EventData::EnemyHealth { id, health }))
+}
+
+// i32, bool, i32
+fn decode_enemy_target<'a, 't>(
+    i: &'a [u8],
+    _: &DataType<'t>,
+    _: &Typemap<'t>,
+) -> PResult<'a, EventData> {
+    let (i, (id, has_target, target)) = tuple((le_i32, parse_replay_bool, le_i32))(i)?;
+
+    Ok((
+        i,
+        EventData::EnemyTarget {
+            id,
+            target: has_target.then_some(target),
+        },
+    ))
+}
+
+// i32, u8
+fn decode_enemy_behaviour<'a, 't>(
+    i: &'a [u8],
+    _: &DataType<'t>,
+    _: &Typemap<'t>,
+) -> PResult<'a, EventData> {
+    let (i, (id, behaviour)) = pair(le_i32, le_u8)(i)?;
+
+    Ok((
+        i,
+        EventData::EnemyBehaviour {
+            id,
+            behaviour: EnemyBehaviour::from_repr(behaviour).unwrap_or_default(),
+        },
+    ))
+}
+
+// u8, f32 * 3, f16 * 3 + u8
+fn decode_transform<'a>(i: &'a [u8], _: &DataType<'_>) -> PResult<'a, DynamicData> {
+    let (i, common) = parse_commons(i)?;
+
+    Ok((i, DynamicData::Transform(common)))
+}
+
+// f16, f16
+fn decode_player_stats<'a>(i: &'a [u8], _: &DataType<'_>) -> PResult<'a, DynamicData> {
+    let (i, (health, infection)) = pair(le_f16, le_f16)(i)?;
+
+    Ok((
+        i,
+        DynamicData::PlayerStats(PlayerStats { health, infection }),
+    ))
+}
+
+// identifier, u8, identifier list, u8, identifier list
+fn decode_player_backpack<'a>(i: &'a [u8], _: &DataType<'_>) -> PResult<'a, DynamicData> {
+    let identifier = |i| parse_replay_identifier_type(i).map(|(i, item)| (i, item.into_owned()));
+    let (i, equipped) = identifier(i)?;
+    let (i, num_slots) = le_u8(i)?;
+    let (i, slots) = count(identifier, num_slots.into())(i)?;
+    let (i, num_vanity) = le_u8(i)?;
+    let (i, vanity) = count(identifier, num_vanity.into())(i)?;
+
+    Ok((
+        i,
+        DynamicData::PlayerBackpack(PlayerBackpack {
+            equipped,
+            slots,
+            vanity,
+        }),
+    ))
+}
@@ -0,0 +1,130 @@
+//! Overlay linking bulkhead controllers to the doors they open.
+
+use bevy::{
+    color::palettes::css::{CRIMSON, MEDIUM_PURPLE, ORANGE},
+    prelude::*,
+};
+
+use crate::{
+    dimensions::CurrentDimension,
+    replay::{common_transform, CurrentReplay, ReplayAsset, ReplayLoaded, SpawnReplay},
+    settings::{set_button_text, spawn_settings_panel, toggle_text, SettingsPanel},
+    theme::prelude::*,
+};
+
+/// Lines start and end this far above the floor, to clear the controller and
+/// the bottom of the door.
+const LINK_HEIGHT: f32 = 1.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShowBulkheadLinks>()
+        .init_resource::<BulkheadLinks>()
+        .add_systems(Startup, spawn_links_toggle.after(spawn_settings_panel))
+        .add_systems(
+            Update,
+            (
+                find_links
+                    .in_set(SpawnReplay)
+                    .run_if(on_event::<ReplayLoaded>()),
+                draw_links.run_if(|show: Res<ShowBulkheadLinks>| show.0),
+                update_links_toggle.run_if(resource_changed::<ShowBulkheadLinks>),
+            ),
+        );
+}
+
+#[derive(Resource, Debug)]
+pub struct ShowBulkheadLinks(pub bool);
+
+impl Default for ShowBulkheadLinks {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// A line from a controller to one of its doors.
+#[derive(Debug)]
+pub struct BulkheadLink {
+    pub dimension: u8,
+    pub controller: Vec3,
+    pub door: Vec3,
+    pub color: Color,
+}
+
+/// Links of the bulkhead controllers in the current replay.
+#[derive(Resource, Debug, Default)]
+pub struct BulkheadLinks(pub Vec<BulkheadLink>);
+
+#[derive(Component, Debug)]
+struct LinksToggle;
+
+fn find_links(
+    mut links: ResMut<BulkheadLinks>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    links.0.clear();
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+    let header = &replay.header;
+
+    for controller in &header.bulkhead_controllers {
+        let Some(common) = header.common(controller.idx) else {
+            continue;
+        };
+        let start = common_transform(common).translation + Vec3::Y * LINK_HEIGHT;
+        let doors = controller.doors(header);
+        for (door, color) in [
+            (doors.main, CRIMSON),
+            (doors.secondary, ORANGE),
+            (doors.ovl, MEDIUM_PURPLE),
+        ] {
+            let Some(door) = door.and_then(|door| header.common(door.idx)) else {
+                continue;
+            };
+            links.0.push(BulkheadLink {
+                dimension: common.dimension,
+                controller: start,
+                door: common_transform(door).translation + Vec3::Y * LINK_HEIGHT,
+                color: color.into(),
+            });
+        }
+    }
+}
+
+fn draw_links(mut gizmos: Gizmos, links: Res<BulkheadLinks>, dimension: Res<CurrentDimension>) {
+    for link in links.0.iter().filter(|link| link.dimension == dimension.0) {
+        gizmos.line(link.controller, link.door, link.color);
+        gizmos.sphere(link.door, Quat::IDENTITY, 0.3, link.color);
+    }
+}
+
+fn spawn_links_toggle(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+    show: Res<ShowBulkheadLinks>,
+) {
+    let Ok(panel) = q_panel.get_single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|children| {
+        children
+            .small_button(toggle_text("Bulkhead Links", show.0))
+            .insert(LinksToggle)
+            .observe(toggle_links);
+    });
+}
+
+fn toggle_links(_trigger: Trigger<OnPress>, mut show: ResMut<ShowBulkheadLinks>) {
+    show.0 = !show.0;
+}
+
+fn update_links_toggle(
+    show: Res<ShowBulkheadLinks>,
+    q_toggle: Query<&Children, With<LinksToggle>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for children in &q_toggle {
+        set_button_text(children, &mut q_text, toggle_text("Bulkhead Links", show.0));
+    }
+}
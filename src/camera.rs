@@ -0,0 +1,366 @@
+//! Camera orbiting the level, or looking straight down at it.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::camera::ScalingMode,
+    utils::HashMap,
+};
+use rrv_core::prelude::Geometry;
+
+use crate::{
+    dimensions::CurrentDimension,
+    follow::FollowPlayer,
+    replay::{CurrentReplay, ReplayAsset, ReplayLoaded, SpawnReplay},
+    settings::{set_button_text, spawn_settings_panel, toggle_text, SettingsPanel},
+    theme::prelude::*,
+};
+
+/// Radians the orbit camera turns per pixel of mouse movement.
+const ROTATE_SPEED: f32 = 0.005;
+
+/// How much one line of scrolling zooms in or out.
+const ZOOM_STEP: f32 = 0.1;
+
+/// Just short of straight up or down, where yaw would stop making sense.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+const MIN_DISTANCE: f32 = 1.;
+const MAX_DISTANCE: f32 = 2000.;
+
+/// Framed bounds are padded by this factor, so the level does not touch the
+/// edges of the window.
+const FRAME_MARGIN: f32 = 1.2;
+
+/// Pitch the level is framed at, looking down at it.
+const FRAME_PITCH: f32 = -0.9;
+const FRAME_YAW: f32 = 0.6;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TopDown>()
+        .init_resource::<LevelBounds>()
+        .add_systems(
+            Startup,
+            (
+                spawn_camera,
+                spawn_top_down_toggle.after(spawn_settings_panel),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                find_level_bounds
+                    .in_set(SpawnReplay)
+                    .run_if(on_event::<ReplayLoaded>()),
+                // A followed player switching dimension keeps the camera on them.
+                frame_level.after(SpawnReplay).run_if(
+                    on_event::<ReplayLoaded>().or_else(
+                        resource_changed::<CurrentDimension>
+                            .and_then(|follow: Res<FollowPlayer>| follow.0.is_none()),
+                    ),
+                ),
+                (camera_controls, apply_camera).chain().after(frame_level),
+                update_top_down_toggle.run_if(resource_changed::<TopDown>),
+            ),
+        );
+}
+
+/// Camera looking at [`focus`](Self::focus) from `distance` away. Mouse input
+/// moves it, and [`apply_camera`] turns it into the camera transform.
+#[derive(Component, Debug)]
+pub struct ReplayCamera {
+    pub focus: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl Default for ReplayCamera {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::new(0., 1., 0.),
+            yaw: 0.,
+            pitch: -0.45,
+            distance: 15.,
+        }
+    }
+}
+
+impl ReplayCamera {
+    /// Looks at the box from `min` to `max`, far enough back to see all of it.
+    pub fn frame(&mut self, min: Vec3, max: Vec3, fov: f32) {
+        let radius = (max - min).length() / 2.;
+        self.focus = (min + max) / 2.;
+        self.yaw = FRAME_YAW;
+        self.pitch = FRAME_PITCH;
+        self.distance =
+            (radius * FRAME_MARGIN / (fov / 2.).tan()).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    /// Turns to look along `direction`.
+    pub fn look_to(&mut self, direction: Vec3) {
+        let Some(direction) = direction.try_normalize() else {
+            return;
+        };
+        self.yaw = f32::atan2(-direction.x, -direction.z);
+        self.pitch = direction.y.asin().clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    fn rotation(&self, top_down: bool) -> Quat {
+        if top_down {
+            // North of the map is at the top of the window.
+            Quat::from_rotation_x(-FRAC_PI_2)
+        } else {
+            Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.)
+        }
+    }
+
+    fn transform(&self, top_down: bool) -> Transform {
+        let rotation = self.rotation(top_down);
+        Transform::from_translation(self.focus + rotation * Vec3::Z * self.distance)
+            .with_rotation(rotation)
+    }
+}
+
+/// Whether the camera looks straight down with an orthographic projection.
+#[derive(Resource, Debug, Default)]
+pub struct TopDown(pub bool);
+
+/// Bounding box of the level geometry in each dimension.
+#[derive(Resource, Debug, Default)]
+pub struct LevelBounds(pub HashMap<u8, (Vec3, Vec3)>);
+
+#[derive(Component, Debug)]
+struct TopDownToggle;
+
+fn spawn_camera(mut commands: Commands) {
+    let camera = ReplayCamera::default();
+    commands.spawn((
+        Name::new("Replay Camera"),
+        Camera3dBundle {
+            transform: camera.transform(false),
+            ..default()
+        },
+        camera,
+    ));
+}
+
+/// Bounding boxes of the geometry, by dimension.
+fn level_bounds(level_geometry: &[Geometry]) -> HashMap<u8, (Vec3, Vec3)> {
+    let mut bounds = HashMap::new();
+    for geometry in level_geometry {
+        for vertex in &geometry.vertices {
+            let vertex = Vec3::from_array(vertex.to_array());
+            bounds
+                .entry(geometry.dimension)
+                .and_modify(|(min, max): &mut (Vec3, Vec3)| {
+                    *min = min.min(vertex);
+                    *max = max.max(vertex);
+                })
+                .or_insert((vertex, vertex));
+        }
+    }
+    bounds
+}
+
+fn find_level_bounds(
+    mut bounds: ResMut<LevelBounds>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+    bounds.0 = level_bounds(&replay.header.level_geometry);
+}
+
+fn frame_level(
+    bounds: Res<LevelBounds>,
+    dimension: Res<CurrentDimension>,
+    mut q_camera: Query<(&mut ReplayCamera, &Projection)>,
+) {
+    let Some(&(min, max)) = bounds.0.get(&dimension.0) else {
+        return;
+    };
+    for (mut camera, projection) in &mut q_camera {
+        let fov = match projection {
+            Projection::Perspective(perspective) => perspective.fov,
+            Projection::Orthographic(_) => PerspectiveProjection::default().fov,
+        };
+        camera.frame(min, max, fov);
+    }
+}
+
+fn camera_controls(
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut top_down: ResMut<TopDown>,
+    mut q_camera: Query<(&mut ReplayCamera, &Camera, &Projection)>,
+) {
+    let delta: Vec2 = motion.read().map(|motion| motion.delta).sum();
+    let scroll: f32 = wheel
+        .read()
+        .map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / 20.,
+        })
+        .sum();
+    if keyboard.just_pressed(KeyCode::KeyT) {
+        top_down.0 = !top_down.0;
+    }
+
+    for (mut camera, view, projection) in &mut q_camera {
+        // The dev tools fly camera takes over while it is active.
+        if !view.is_active {
+            continue;
+        }
+
+        let pan = buttons.pressed(MouseButton::Middle)
+            || (top_down.0 && buttons.pressed(MouseButton::Right))
+            || (buttons.pressed(MouseButton::Right) && keyboard.pressed(KeyCode::ShiftLeft));
+        if pan && delta != Vec2::ZERO {
+            // Scaled so the level moves along with the cursor.
+            let height = match projection {
+                Projection::Orthographic(orthographic) => orthographic.area.height(),
+                Projection::Perspective(perspective) => {
+                    2. * camera.distance * (perspective.fov / 2.).tan()
+                }
+            };
+            let pixels = view
+                .logical_viewport_size()
+                .map_or(1., |size| size.y.max(1.));
+            let rotation = camera.rotation(top_down.0);
+            let offset = rotation * Vec3::new(-delta.x, delta.y, 0.) * height / pixels;
+            camera.focus += offset;
+        } else if buttons.pressed(MouseButton::Right) && !top_down.0 {
+            camera.yaw -= delta.x * ROTATE_SPEED;
+            camera.pitch = (camera.pitch - delta.y * ROTATE_SPEED).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        if scroll != 0. {
+            let distance = camera.distance * (1. - scroll * ZOOM_STEP);
+            camera.distance = distance.clamp(MIN_DISTANCE, MAX_DISTANCE);
+        }
+    }
+}
+
+/// Places the camera from its [`ReplayCamera`], switching projection to match
+/// [`TopDown`].
+pub fn apply_camera(
+    top_down: Res<TopDown>,
+    mut q_camera: Query<(Ref<ReplayCamera>, &mut Transform, &mut Projection)>,
+) {
+    for (camera, mut transform, mut projection) in &mut q_camera {
+        if !camera.is_changed() && !top_down.is_changed() {
+            continue;
+        }
+        *transform = camera.transform(top_down.0);
+
+        // The orthographic view shows as much of the level as the perspective
+        // one does at the focus.
+        let fov = PerspectiveProjection::default().fov;
+        let height = 2. * camera.distance * (fov / 2.).tan();
+        match (top_down.0, projection.as_mut()) {
+            (true, Projection::Orthographic(orthographic)) => {
+                orthographic.scaling_mode = ScalingMode::FixedVertical(height);
+            }
+            (true, _) => {
+                *projection = Projection::Orthographic(OrthographicProjection {
+                    scaling_mode: ScalingMode::FixedVertical(height),
+                    // Keep geometry above the focus, but behind the camera, in view.
+                    near: -MAX_DISTANCE,
+                    far: MAX_DISTANCE,
+                    ..default()
+                });
+            }
+            (false, Projection::Perspective(_)) => {}
+            (false, _) => *projection = Projection::Perspective(default()),
+        }
+    }
+}
+
+fn spawn_top_down_toggle(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+    top_down: Res<TopDown>,
+) {
+    let Ok(panel) = q_panel.get_single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|children| {
+        children
+            .small_button(toggle_text("Top-Down View", top_down.0))
+            .insert(TopDownToggle)
+            .observe(toggle_top_down);
+    });
+}
+
+fn toggle_top_down(_trigger: Trigger<OnPress>, mut top_down: ResMut<TopDown>) {
+    top_down.0 = !top_down.0;
+}
+
+fn update_top_down_toggle(
+    top_down: Res<TopDown>,
+    q_toggle: Query<&Children, With<TopDownToggle>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for children in &q_toggle {
+        set_button_text(
+            children,
+            &mut q_text,
+            toggle_text("Top-Down View", top_down.0),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_cover_geometry_of_each_dimension() {
+        let geometry = |dimension, vertices: &[[f32; 3]]| Geometry {
+            dimension,
+            vertices: vertices
+                .iter()
+                .map(|&v| glam::Vec3::from_array(v))
+                .collect(),
+            indices: vec![],
+        };
+        let bounds = level_bounds(&[
+            geometry(0, &[[0., 0., 0.], [4., -2., 1.]]),
+            geometry(1, &[[10., 10., 10.]]),
+            geometry(0, &[[-3., 5., 2.]]),
+        ]);
+
+        assert_eq!(bounds[&0], (Vec3::new(-3., -2., 0.), Vec3::new(4., 5., 2.)));
+        assert_eq!(bounds[&1], (Vec3::splat(10.), Vec3::splat(10.)));
+    }
+
+    #[test]
+    fn framing_looks_at_center_of_bounds() {
+        let mut camera = ReplayCamera::default();
+        let fov = PerspectiveProjection::default().fov;
+        camera.frame(Vec3::new(-10., 0., -10.), Vec3::new(10., 4., 10.), fov);
+
+        assert_eq!(camera.focus, Vec3::new(0., 2., 0.));
+        let transform = camera.transform(false);
+        let to_focus = (camera.focus - transform.translation).normalize();
+        assert!(transform.forward().dot(to_focus) > 0.999);
+        assert!(transform.translation.y > camera.focus.y);
+    }
+
+    #[test]
+    fn looks_along_direction() {
+        let mut camera = ReplayCamera::default();
+        let direction = Vec3::new(1., -0.5, 2.).normalize();
+        camera.look_to(direction);
+
+        let forward = camera.transform(false).forward();
+        assert!(forward.dot(direction) > 0.999);
+    }
+}
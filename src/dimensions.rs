@@ -0,0 +1,132 @@
+//! Levels span several dimensions, only the current one is shown.
+
+use bevy::prelude::*;
+
+use crate::{
+    replay::{CurrentReplay, ReplayAsset, ReplayLoaded, SpawnReplay},
+    settings::{set_button_text, spawn_settings_panel, SettingsPanel},
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CurrentDimension>()
+        .init_resource::<Dimensions>()
+        .add_systems(
+            Startup,
+            spawn_dimension_switcher.after(spawn_settings_panel),
+        )
+        .add_systems(
+            Update,
+            (
+                find_dimensions
+                    .in_set(SpawnReplay)
+                    .run_if(on_event::<ReplayLoaded>()),
+                apply_dimension_visibility.after(SpawnReplay),
+                update_dimension_switcher.run_if(resource_changed::<CurrentDimension>),
+            ),
+        );
+}
+
+/// The dimension being shown.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentDimension(pub u8);
+
+/// Dimensions of the current replay, in ascending order.
+#[derive(Resource, Debug, Default)]
+pub struct Dimensions(pub Vec<u8>);
+
+/// The dimension an entity is in. It is hidden unless that is the [`CurrentDimension`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dimension(pub u8);
+
+#[derive(Component, Debug)]
+struct DimensionSwitcher;
+
+pub fn dimension_name(dimension: u8) -> String {
+    match dimension {
+        0 => "Reality".to_string(),
+        n => format!("Dimension {n}"),
+    }
+}
+
+fn find_dimensions(
+    mut dimensions: ResMut<Dimensions>,
+    mut current_dimension: ResMut<CurrentDimension>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    let header = &replay.header;
+    dimensions.0 = header
+        .level_geometry
+        .iter()
+        .map(|geometry| geometry.dimension)
+        .chain(header.commons.iter().map(|common| common.dimension))
+        .collect();
+    dimensions.0.sort_unstable();
+    dimensions.0.dedup();
+
+    let first = dimensions.0.first().copied().unwrap_or_default();
+    current_dimension.set_if_neq(CurrentDimension(first));
+}
+
+fn apply_dimension_visibility(
+    current: Res<CurrentDimension>,
+    mut q_dimensions: Query<(Ref<Dimension>, &mut Visibility)>,
+) {
+    for (dimension, mut visibility) in &mut q_dimensions {
+        if !current.is_changed() && !dimension.is_changed() {
+            continue;
+        }
+        visibility.set_if_neq(if dimension.0 == current.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+fn spawn_dimension_switcher(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+    current: Res<CurrentDimension>,
+) {
+    let Ok(panel) = q_panel.get_single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|children| {
+        children
+            .small_button(dimension_name(current.0))
+            .insert(DimensionSwitcher)
+            .observe(next_dimension);
+    });
+}
+
+/// Switches to the next dimension of the replay, wrapping around after the last.
+fn next_dimension(
+    _trigger: Trigger<OnPress>,
+    dimensions: Res<Dimensions>,
+    mut current: ResMut<CurrentDimension>,
+) {
+    let next = dimensions
+        .0
+        .iter()
+        .find(|&&dimension| dimension > current.0)
+        .or(dimensions.0.first());
+    if let Some(&next) = next {
+        current.set_if_neq(CurrentDimension(next));
+    }
+}
+
+fn update_dimension_switcher(
+    current: Res<CurrentDimension>,
+    q_switcher: Query<&Children, With<DimensionSwitcher>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for children in &q_switcher {
+        set_button_text(children, &mut q_text, dimension_name(current.0));
+    }
+}
@@ -0,0 +1,158 @@
+//! Doors of the current replay.
+
+use bevy::{
+    color::palettes::css::{CRIMSON, GOLD, LIME, MEDIUM_PURPLE, ORANGE_RED, SLATE_GRAY},
+    prelude::*,
+    utils::HashMap,
+};
+use rrv_core::prelude::{DoorSize, DoorState, DoorVariant};
+
+use crate::{
+    dimensions::Dimension,
+    playback::{ApplySnapshots, ReplayWorld},
+    replay::{
+        common_transform, CurrentReplay, ReplayAsset, ReplayEntity, ReplayLoaded, SpawnReplay,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            spawn_doors
+                .in_set(SpawnReplay)
+                .run_if(on_event::<ReplayLoaded>()),
+            update_doors
+                .after(ApplySnapshots)
+                .run_if(resource_changed::<ReplayWorld>),
+        ),
+    );
+}
+
+/// The panel of a door, hidden while it is open or destroyed.
+#[derive(Component, Debug)]
+pub struct Door {
+    pub id: i32,
+}
+
+/// Marker for the band above checkpoint doors.
+#[derive(Component, Debug)]
+pub struct Checkpoint;
+
+/// Width and height of the doorway.
+fn door_extents(size: DoorSize) -> Vec2 {
+    match size {
+        DoorSize::Small => Vec2::new(4., 4.),
+        DoorSize::Medium => Vec2::new(8., 6.),
+        DoorSize::Large => Vec2::new(16., 8.),
+    }
+}
+
+fn door_thickness(variant: DoorVariant) -> f32 {
+    match variant {
+        DoorVariant::WeakDoor => 0.2,
+        DoorVariant::SecurityDoor => 0.5,
+        DoorVariant::BulkheadDoor | DoorVariant::BulkheadDoorMain => 1.,
+        DoorVariant::ApexDoor => 0.8,
+    }
+}
+
+fn door_color(variant: DoorVariant) -> Color {
+    match variant {
+        DoorVariant::WeakDoor => SLATE_GRAY,
+        DoorVariant::SecurityDoor => GOLD,
+        DoorVariant::BulkheadDoor => ORANGE_RED,
+        DoorVariant::BulkheadDoorMain => CRIMSON,
+        DoorVariant::ApexDoor => MEDIUM_PURPLE,
+    }
+    .into()
+}
+
+fn spawn_doors(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    let mut door_meshes = HashMap::new();
+    let mut door_materials = HashMap::new();
+    let mut checkpoint_meshes = HashMap::new();
+    let checkpoint_mat = materials.add(StandardMaterial {
+        base_color: LIME.into(),
+        emissive: LinearRgba::from(LIME) * 2.,
+        ..default()
+    });
+
+    for door in &replay.header.doors {
+        let Some(common) = replay.header.commons.get(door.idx) else {
+            continue;
+        };
+        let extents = door_extents(door.size);
+        let mesh = door_meshes
+            .entry((door.variant, door.size))
+            .or_insert_with(|| {
+                meshes.add(Cuboid::new(
+                    extents.x,
+                    extents.y,
+                    door_thickness(door.variant),
+                ))
+            })
+            .clone();
+        let material = door_materials
+            .entry(door.variant)
+            .or_insert_with(|| materials.add(door_color(door.variant)))
+            .clone();
+        let checkpoint_mesh = door.checkpoint.then(|| {
+            checkpoint_meshes
+                .entry(door.size)
+                .or_insert_with(|| meshes.add(Cuboid::new(extents.x, 0.3, 0.3)))
+                .clone()
+        });
+
+        commands
+            .spawn((
+                Name::new(format!("Door {}", door.serial)),
+                SpatialBundle::from_transform(common_transform(common)),
+                Dimension(common.dimension),
+                ReplayEntity,
+            ))
+            .with_children(|children| {
+                children.spawn((
+                    Door { id: door.id },
+                    PbrBundle {
+                        mesh,
+                        material,
+                        transform: Transform::from_xyz(0., extents.y / 2., 0.),
+                        ..default()
+                    },
+                ));
+                if let Some(mesh) = checkpoint_mesh {
+                    children.spawn((
+                        Name::new("Checkpoint"),
+                        Checkpoint,
+                        PbrBundle {
+                            mesh,
+                            material: checkpoint_mat.clone(),
+                            transform: Transform::from_xyz(0., extents.y + 0.5, 0.),
+                            ..default()
+                        },
+                    ));
+                }
+            });
+    }
+}
+
+fn update_doors(world: Res<ReplayWorld>, mut q_doors: Query<(&Door, &mut Visibility)>) {
+    for (door, mut visibility) in &mut q_doors {
+        let state = world.doors.get(&door.id).copied().unwrap_or_default();
+        visibility.set_if_neq(match state {
+            DoorState::Closed | DoorState::Glued => Visibility::Inherited,
+            DoorState::Open | DoorState::Destroyed => Visibility::Hidden,
+        });
+    }
+}
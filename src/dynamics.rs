@@ -0,0 +1,50 @@
+//! Entities moved by the snapshots, like players and enemies.
+
+use bevy::prelude::*;
+
+use crate::{
+    dimensions::CurrentDimension,
+    playback::{ApplySnapshots, Interpolated},
+    replay::common_transform,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DynamicEntity>()
+        .add_systems(Update, place_dynamic_entities.after(ApplySnapshots));
+}
+
+/// An entity placed at the [`Interpolated`] transform of the replay entity
+/// with this type id and id. It is shown while that entity exists in the
+/// [`CurrentDimension`], so it doesn't take a [`Dimension`](crate::dimensions::Dimension).
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct DynamicEntity {
+    /// Type of the entity in the typemap, ids are only unique within a type.
+    pub type_id: u16,
+    pub id: i32,
+}
+
+fn place_dynamic_entities(
+    interpolated: Res<Interpolated>,
+    dimension: Res<CurrentDimension>,
+    mut q_dynamics: Query<(&DynamicEntity, &mut Transform, &mut Visibility)>,
+) {
+    if !interpolated.is_changed() && !dimension.is_changed() {
+        return;
+    }
+
+    for (entity, mut transform, mut visibility) in &mut q_dynamics {
+        let Some(common) = interpolated.0.get(&(entity.type_id, entity.id)) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        let placed = common_transform(common);
+        transform.translation = placed.translation;
+        transform.rotation = placed.rotation;
+        visibility.set_if_neq(if common.dimension == dimension.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
@@ -0,0 +1,354 @@
+//! Enemies, colored by how aggressive they are, with a tooltip on hover.
+//!
+//! Kinds, health, targets and behaviour come from enemy layouts that are only
+//! decoded with the `provisional_enemies` feature. Without it enemies show up
+//! by their transform alone.
+
+use bevy::{
+    color::palettes::css::{CRIMSON, DIM_GRAY, GOLD, MEDIUM_PURPLE, STEEL_BLUE, TEAL},
+    prelude::*,
+    utils::HashMap,
+    window::PrimaryWindow,
+};
+use rrv_core::prelude::{EnemyBehaviour, EventData, IdentifierType, SpawnInfo};
+
+use crate::{
+    dynamics::DynamicEntity,
+    playback::{ApplySnapshots, ReplayWorld},
+    players::Players,
+    replay::{CurrentReplay, ReplayAsset, ReplayEntity, ReplayLoaded, SpawnReplay},
+    settings::{set_button_text, spawn_settings_panel, toggle_text, SettingsPanel},
+    theme::prelude::*,
+};
+
+/// Enemies are picked by the cursor within this distance of their middle.
+const HOVER_RADIUS: f32 = 0.8;
+
+const BODY_HEIGHT: f32 = 1.8;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AggroColors>()
+        .init_resource::<EnemyMaterials>()
+        .add_systems(
+            Startup,
+            (
+                spawn_tooltip,
+                spawn_aggro_toggle.after(spawn_settings_panel),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                spawn_enemies
+                    .in_set(SpawnReplay)
+                    .run_if(on_event::<ReplayLoaded>()),
+                color_enemies
+                    .after(ApplySnapshots)
+                    .after(SpawnReplay)
+                    .run_if(
+                        resource_changed::<ReplayWorld>.or_else(resource_changed::<AggroColors>),
+                    ),
+                show_tooltip.after(ApplySnapshots),
+                update_aggro_toggle.run_if(resource_changed::<AggroColors>),
+            ),
+        );
+}
+
+/// Whether enemies are colored by their [`EnemyBehaviour`].
+#[derive(Resource, Debug)]
+pub struct AggroColors(pub bool);
+
+impl Default for AggroColors {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Enemy {
+    /// Type of the enemy in the typemap.
+    pub type_id: u16,
+    pub id: i32,
+    pub kind: IdentifierType<'static>,
+    pub max_health: f32,
+}
+
+/// Shared enemy materials, one for each behaviour and one for when
+/// [`AggroColors`] is off.
+#[derive(Resource, Debug)]
+struct EnemyMaterials {
+    neutral: Handle<StandardMaterial>,
+    behaviours: HashMap<EnemyBehaviour, Handle<StandardMaterial>>,
+}
+
+impl FromWorld for EnemyMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let behaviours = [
+            (EnemyBehaviour::Hibernating, STEEL_BLUE),
+            (EnemyBehaviour::Patrolling, TEAL),
+            (EnemyBehaviour::Investigating, GOLD),
+            (EnemyBehaviour::Scouting, MEDIUM_PURPLE),
+            (EnemyBehaviour::Combat, CRIMSON),
+        ]
+        .into_iter()
+        .map(|(behaviour, color)| (behaviour, materials.add(Color::from(color))))
+        .collect();
+
+        Self {
+            neutral: materials.add(Color::from(DIM_GRAY)),
+            behaviours,
+        }
+    }
+}
+
+impl EnemyMaterials {
+    fn get(&self, behaviour: EnemyBehaviour, aggro_colors: bool) -> Handle<StandardMaterial> {
+        if !aggro_colors {
+            return self.neutral.clone();
+        }
+        self.behaviours
+            .get(&behaviour)
+            .unwrap_or(&self.neutral)
+            .clone()
+    }
+}
+
+#[derive(Component, Debug)]
+struct EnemyTooltip;
+
+#[derive(Component, Debug)]
+struct AggroToggle;
+
+fn spawn_enemies(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<EnemyMaterials>,
+    aggro_colors: Res<AggroColors>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    // Standing on the origin, like the other dynamic entities.
+    let mesh = meshes.add(
+        Mesh::from(Capsule3d::new(0.35, BODY_HEIGHT - 0.7))
+            .translated_by(Vec3::Y * BODY_HEIGHT / 2.),
+    );
+
+    let mut enemies: HashMap<i32, Enemy> = HashMap::new();
+    for snapshot in &replay.snapshots {
+        for event in &snapshot.events {
+            if let EventData::Spawn {
+                type_id,
+                id,
+                info: Some(SpawnInfo::Enemy(ref info)),
+                ..
+            } = event.data
+            {
+                enemies.entry(id).or_insert_with(|| Enemy {
+                    type_id,
+                    id,
+                    kind: info.kind.clone(),
+                    max_health: info.max_health,
+                });
+            }
+        }
+        // Recorders before `Vanilla.Enemy` 0.0.2 only record the transform.
+        for dynamic in &snapshot.dynamics {
+            let is_enemy = replay
+                .typemap
+                .get(dynamic.type_id)
+                .is_some_and(|t| t.typename == "Vanilla.Enemy");
+            if is_enemy {
+                enemies.entry(dynamic.id).or_insert_with(|| Enemy {
+                    type_id: dynamic.type_id,
+                    id: dynamic.id,
+                    kind: IdentifierType::Unknown,
+                    max_health: 0.,
+                });
+            }
+        }
+    }
+
+    for enemy in enemies.into_values() {
+        commands.spawn((
+            Name::new(format!("Enemy {}", enemy.id)),
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: materials.get(EnemyBehaviour::default(), aggro_colors.0),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DynamicEntity {
+                type_id: enemy.type_id,
+                id: enemy.id,
+            },
+            enemy,
+            ReplayEntity,
+        ));
+    }
+}
+
+fn color_enemies(
+    world: Res<ReplayWorld>,
+    materials: Res<EnemyMaterials>,
+    aggro_colors: Res<AggroColors>,
+    mut q_enemies: Query<(&Enemy, &mut Handle<StandardMaterial>)>,
+) {
+    for (enemy, mut material) in &mut q_enemies {
+        let behaviour = world
+            .enemies
+            .get(&enemy.id)
+            .map(|state| state.behaviour)
+            .unwrap_or_default();
+        let handle = materials.get(behaviour, aggro_colors.0);
+        if *material != handle {
+            *material = handle;
+        }
+    }
+}
+
+fn spawn_tooltip(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Enemy Tooltip"),
+        EnemyTooltip,
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: ui_palette::BUTTON_TEXT,
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            background_color: ui_palette::TIMELINE_BAR_BACKGROUND.into(),
+            visibility: Visibility::Hidden,
+            // Above the rest of the UI.
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+    ));
+}
+
+/// Distance along `ray` to the enemy standing at `position`, if it passes
+/// close enough to its middle.
+fn hover_distance(ray: Ray3d, position: Vec3) -> Option<f32> {
+    let center = position + Vec3::Y * BODY_HEIGHT / 2.;
+    let distance = (center - ray.origin).dot(*ray.direction);
+    let closest = ray.get_point(distance);
+    (distance >= 0. && closest.distance(center) <= HOVER_RADIUS).then_some(distance)
+}
+
+fn tooltip_text(enemy: &Enemy, world: &ReplayWorld, players: &Players) -> String {
+    let state = world.enemies.get(&enemy.id).copied().unwrap_or_default();
+    let target = match state.target {
+        Some(id) => players
+            .0
+            .iter()
+            .find(|player| player.id == id)
+            .map_or_else(|| format!("Agent {id}"), |player| player.name.clone()),
+        None => "None".to_string(),
+    };
+    format!(
+        "{}\nHealth: {:.0} / {:.0}\nTarget: {target}\n{:?}",
+        enemy.kind, state.health, enemy.max_health, state.behaviour
+    )
+}
+
+fn show_tooltip(
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    q_enemies: Query<(&Enemy, &GlobalTransform, &InheritedVisibility)>,
+    mut q_tooltip: Query<(&mut Text, &mut Style, &mut Visibility), With<EnemyTooltip>>,
+    world: Res<ReplayWorld>,
+    players: Res<Players>,
+) {
+    let Ok((mut text, mut style, mut visibility)) = q_tooltip.get_single_mut() else {
+        return;
+    };
+    let cursor = q_window
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    let camera = q_camera.iter().find(|(camera, _)| camera.is_active);
+    let ray = cursor
+        .zip(camera)
+        .and_then(|(cursor, (camera, transform))| camera.viewport_to_world(transform, cursor));
+
+    let hovered = ray.and_then(|ray| {
+        q_enemies
+            .iter()
+            .filter(|(_, _, shown)| shown.get())
+            .filter_map(|(enemy, transform, _)| {
+                hover_distance(ray, transform.translation()).map(|distance| (enemy, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    });
+    let (Some(cursor), Some((enemy, _))) = (cursor, hovered) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+
+    text.sections[0].value = tooltip_text(enemy, &world, &players);
+    style.left = Val::Px(cursor.x + 16.);
+    style.top = Val::Px(cursor.y + 16.);
+    visibility.set_if_neq(Visibility::Inherited);
+}
+
+fn spawn_aggro_toggle(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+    aggro_colors: Res<AggroColors>,
+) {
+    let Ok(panel) = q_panel.get_single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|children| {
+        children
+            .small_button(toggle_text("Aggro Colors", aggro_colors.0))
+            .insert(AggroToggle)
+            .observe(toggle_aggro_colors);
+    });
+}
+
+fn toggle_aggro_colors(_trigger: Trigger<OnPress>, mut aggro_colors: ResMut<AggroColors>) {
+    aggro_colors.0 = !aggro_colors.0;
+}
+
+fn update_aggro_toggle(
+    aggro_colors: Res<AggroColors>,
+    q_toggle: Query<&Children, With<AggroToggle>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for children in &q_toggle {
+        set_button_text(
+            children,
+            &mut q_text,
+            toggle_text("Aggro Colors", aggro_colors.0),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hovers_enemies_near_the_ray() {
+        let ray = Ray3d::new(Vec3::new(0., 0.9, 10.), Vec3::NEG_Z);
+
+        assert_eq!(hover_distance(ray, Vec3::ZERO), Some(10.));
+        assert!(hover_distance(ray, Vec3::new(0.5, 0., 5.)).is_some());
+        assert!(hover_distance(ray, Vec3::new(2., 0., 5.)).is_none());
+        // Behind the camera.
+        assert!(hover_distance(ray, Vec3::new(0., 0., 20.)).is_none());
+    }
+}
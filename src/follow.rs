@@ -0,0 +1,241 @@
+//! Camera following a player through the replay.
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::{apply_camera, ReplayCamera},
+    dimensions::CurrentDimension,
+    playback::{ApplySnapshots, Interpolated},
+    players::Players,
+    replay::{common_transform, ReplayLoaded, SpawnReplay},
+    settings::{set_button_text, spawn_settings_panel, toggle_text, SettingsPanel},
+    theme::prelude::*,
+};
+
+/// The camera looks at this point above the feet of the followed player.
+const EYE_HEIGHT: f32 = 1.6;
+
+/// Keys following the players in the matching slot.
+const PLAYER_KEYS: [KeyCode; 4] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+];
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<FollowPlayer>()
+        .init_resource::<RelativeRotation>()
+        .add_systems(
+            Startup,
+            (spawn_follow_switcher, spawn_relative_rotation_toggle).after(spawn_settings_panel),
+        )
+        .add_systems(
+            Update,
+            (
+                stop_following
+                    .in_set(SpawnReplay)
+                    .run_if(on_event::<ReplayLoaded>()),
+                follow_keys,
+                follow_player
+                    .after(follow_keys)
+                    .after(ApplySnapshots)
+                    .before(apply_camera),
+                update_follow_switcher
+                    .run_if(resource_changed::<FollowPlayer>.or_else(resource_changed::<Players>)),
+                update_relative_rotation_toggle.run_if(resource_changed::<RelativeRotation>),
+            ),
+        );
+}
+
+/// Id of the player the camera follows.
+#[derive(Resource, Debug, Default)]
+pub struct FollowPlayer(pub Option<i32>);
+
+/// Whether the camera turns with the view of the followed player.
+#[derive(Resource, Debug, Default)]
+pub struct RelativeRotation(pub bool);
+
+#[derive(Component, Debug)]
+struct FollowSwitcher;
+
+#[derive(Component, Debug)]
+struct RelativeRotationToggle;
+
+fn stop_following(mut follow: ResMut<FollowPlayer>) {
+    follow.0 = None;
+}
+
+fn follow_text(players: &Players, follow: &FollowPlayer) -> String {
+    let player = follow
+        .0
+        .and_then(|id| players.0.iter().find(|player| player.id == id));
+    match player {
+        Some(player) => format!("Follow: {}", player.name),
+        None => "Follow: Off".to_string(),
+    }
+}
+
+/// Follows the next player who was in `slot`, stopping after the last.
+fn follow_slot(players: &Players, follow: &mut FollowPlayer, slot: u8) {
+    let in_slot: Vec<i32> = players
+        .0
+        .iter()
+        .filter(|player| player.slot == slot)
+        .map(|player| player.id)
+        .collect();
+    follow.0 = match follow
+        .0
+        .and_then(|id| in_slot.iter().position(|&p| p == id))
+    {
+        Some(i) => in_slot.get(i + 1).copied(),
+        None => in_slot.first().copied(),
+    };
+}
+
+fn follow_keys(
+    input: Res<ButtonInput<KeyCode>>,
+    players: Res<Players>,
+    mut follow: ResMut<FollowPlayer>,
+) {
+    for (slot, key) in (0..).zip(PLAYER_KEYS) {
+        if input.just_pressed(key) {
+            follow_slot(&players, &mut follow, slot);
+        }
+    }
+}
+
+fn follow_player(
+    follow: Res<FollowPlayer>,
+    relative: Res<RelativeRotation>,
+    players: Res<Players>,
+    interpolated: Res<Interpolated>,
+    mut dimension: ResMut<CurrentDimension>,
+    mut q_camera: Query<&mut ReplayCamera>,
+) {
+    let Some(id) = follow.0 else {
+        return;
+    };
+    let Some(player) = players.0.iter().find(|player| player.id == id) else {
+        return;
+    };
+    let Some(common) = interpolated.0.get(&(player.type_id, id)) else {
+        return;
+    };
+
+    dimension.set_if_neq(CurrentDimension(common.dimension));
+    let transform = common_transform(common);
+    for mut camera in &mut q_camera {
+        camera.focus = transform.translation + Vec3::Y * EYE_HEIGHT;
+        if relative.0 {
+            camera.look_to(*transform.forward());
+        }
+    }
+}
+
+fn spawn_follow_switcher(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+    players: Res<Players>,
+    follow: Res<FollowPlayer>,
+) {
+    let Ok(panel) = q_panel.get_single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|children| {
+        children
+            .small_button(follow_text(&players, &follow))
+            .insert(FollowSwitcher)
+            .observe(next_player);
+    });
+}
+
+/// Follows the next player, stopping after the last.
+fn next_player(
+    _trigger: Trigger<OnPress>,
+    players: Res<Players>,
+    mut follow: ResMut<FollowPlayer>,
+) {
+    let i = follow
+        .0
+        .and_then(|id| players.0.iter().position(|player| player.id == id));
+    let next = match i {
+        Some(i) => players.0.get(i + 1),
+        None => players.0.first(),
+    };
+    follow.0 = next.map(|player| player.id);
+}
+
+fn update_follow_switcher(
+    players: Res<Players>,
+    follow: Res<FollowPlayer>,
+    q_switcher: Query<&Children, With<FollowSwitcher>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for children in &q_switcher {
+        set_button_text(children, &mut q_text, follow_text(&players, &follow));
+    }
+}
+
+fn spawn_relative_rotation_toggle(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+    relative: Res<RelativeRotation>,
+) {
+    let Ok(panel) = q_panel.get_single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|children| {
+        children
+            .small_button(toggle_text("Relative Rotation", relative.0))
+            .insert(RelativeRotationToggle)
+            .observe(toggle_relative_rotation);
+    });
+}
+
+fn toggle_relative_rotation(_trigger: Trigger<OnPress>, mut relative: ResMut<RelativeRotation>) {
+    relative.0 = !relative.0;
+}
+
+fn update_relative_rotation_toggle(
+    relative: Res<RelativeRotation>,
+    q_toggle: Query<&Children, With<RelativeRotationToggle>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for children in &q_toggle {
+        set_button_text(
+            children,
+            &mut q_text,
+            toggle_text("Relative Rotation", relative.0),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rrv_core::prelude::parse_commons;
+
+    use super::*;
+
+    #[test]
+    fn turns_with_the_view_of_the_player() {
+        // Looking 90 degrees to the right in game, from +z to +x, as a half
+        // quaternion without w.
+        let mut bytes = vec![0];
+        for v in [0., 0., 0.] {
+            bytes.extend(f32::to_le_bytes(v));
+        }
+        bytes.push(3);
+        for bits in [0u16, 0x39a8, 0] {
+            bytes.extend(bits.to_le_bytes());
+        }
+        let (_, common) = parse_commons(&bytes).unwrap();
+
+        let forward = common_transform(&common).forward();
+        assert!(forward.dot(Vec3::X) > 0.999);
+
+        let mut camera = ReplayCamera::default();
+        camera.look_to(*forward);
+        assert!((camera.yaw + std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,307 @@
+//! Level geometry of the current replay.
+
+use bevy::{
+    color::palettes::css::BLACK,
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+    },
+    utils::HashMap,
+};
+use rrv_core::prelude::Geometry;
+
+use crate::{
+    dimensions::Dimension,
+    replay::{CurrentReplay, ReplayAsset, ReplayEntity, ReplayLoaded, SpawnReplay},
+    settings::{set_button_text, spawn_settings_panel, toggle_text, SettingsPanel},
+    theme::prelude::*,
+};
+
+/// Faces meeting at a sharper angle than this get an outline along their edge.
+const CREASE_ANGLE: f32 = 30. * std::f32::consts::PI / 180.;
+
+/// Vertices closer than this are the same vertex when looking for edges.
+const WELD_DISTANCE: f32 = 0.01;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShowOutline>()
+        .add_systems(Startup, spawn_outline_toggle.after(spawn_settings_panel))
+        .add_systems(
+            Update,
+            (
+                spawn_level
+                    .in_set(SpawnReplay)
+                    .run_if(on_event::<ReplayLoaded>()),
+                apply_outline_visibility.run_if(resource_changed::<ShowOutline>),
+            ),
+        );
+}
+
+/// Marker for the meshes of the level geometry.
+#[derive(Component, Debug)]
+pub struct LevelGeometry;
+
+/// Lines along the creases of the level geometry.
+#[derive(Component, Debug)]
+pub struct LevelOutline;
+
+/// Whether the [`LevelOutline`] is drawn.
+#[derive(Resource, Debug)]
+pub struct ShowOutline(pub bool);
+
+impl Default for ShowOutline {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+#[derive(Component, Debug)]
+struct OutlineToggle;
+
+fn spawn_level(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+    show_outline: Res<ShowOutline>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    // Colors come from the vertices.
+    let level_geometry_mat = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        double_sided: true,
+        cull_mode: None,
+        ..default()
+    });
+    let outline_mat = materials.add(StandardMaterial {
+        base_color: BLACK.into(),
+        unlit: true,
+        ..default()
+    });
+    let outline_visibility = if show_outline.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    // Geometry comes in chunks, shade each dimension as a whole.
+    let mut heights: HashMap<u8, (f32, f32)> = HashMap::new();
+    for geometry in &replay.header.level_geometry {
+        let (min, max) = heights
+            .entry(geometry.dimension)
+            .or_insert((f32::MAX, f32::MIN));
+        for v in &geometry.vertices {
+            *min = min.min(v.y);
+            *max = max.max(v.y);
+        }
+    }
+
+    for geometry in &replay.header.level_geometry {
+        let shape: Handle<Mesh> = meshes.add(create_level_geometry_mesh(
+            geometry,
+            heights[&geometry.dimension],
+        ));
+        let outline: Handle<Mesh> = meshes.add(create_level_outline_mesh(geometry));
+        commands
+            .spawn((
+                Name::new("Level Geometry"),
+                PbrBundle {
+                    mesh: shape,
+                    material: level_geometry_mat.clone(),
+                    ..default()
+                },
+                LevelGeometry,
+                Dimension(geometry.dimension),
+                ReplayEntity,
+            ))
+            .with_children(|children| {
+                children.spawn((
+                    Name::new("Level Outline"),
+                    PbrBundle {
+                        mesh: outline,
+                        material: outline_mat.clone(),
+                        visibility: outline_visibility,
+                        ..default()
+                    },
+                    LevelOutline,
+                ));
+            });
+    }
+}
+
+/// Mesh of `geometry`, shaded by height within `heights`. Faces using
+/// vertices that don't exist are left out.
+fn create_level_geometry_mesh(geometry: &Geometry, heights: (f32, f32)) -> Mesh {
+    let vertex_count = geometry.vertices.len();
+    let indices: Vec<u32> = geometry
+        .indices
+        .chunks_exact(3)
+        .filter(|face| {
+            face.iter()
+                .all(|&i| usize::try_from(i).is_ok_and(|i| i < vertex_count))
+        })
+        .flatten()
+        .copied()
+        .collect();
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        geometry
+            .vertices
+            .iter()
+            .map(|v| v.to_array())
+            .collect::<Vec<[f32; 3]>>(),
+    )
+    .with_inserted_indices(Indices::U32(indices));
+
+    // Flat normals keep walls, floors and ramps apart.
+    mesh.duplicate_vertices();
+    mesh.compute_flat_normals();
+
+    let colors = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => {
+            height_colors(positions, heights, geometry.dimension)
+        }
+        _ => vec![],
+    };
+    mesh.with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+}
+
+/// Shades vertices from dark at the lowest point of `heights` to light at the
+/// highest, in a hue picked by the dimension.
+fn height_colors(positions: &[[f32; 3]], (min, max): (f32, f32), dimension: u8) -> Vec<[f32; 4]> {
+    let range = (max - min).max(f32::EPSILON);
+    let hue = (200. + 60. * f32::from(dimension)) % 360.;
+
+    positions
+        .iter()
+        .map(|[_, y, _]| {
+            let t = (y - min) / range;
+            LinearRgba::from(Hsla::hsl(hue, 0.35, 0.25 + 0.5 * t)).to_f32_array()
+        })
+        .collect()
+}
+
+/// Lines along the edges where faces meet at more than [`CREASE_ANGLE`], and
+/// along edges of the geometry that only one face uses. Faces using vertices
+/// that don't exist are left out.
+fn create_level_outline_mesh(geometry: &Geometry) -> Mesh {
+    let key = |v: Vec3| (v / WELD_DISTANCE).round().as_ivec3().to_array();
+    let vertex = |i: u32| {
+        let v = geometry.vertices.get(usize::try_from(i).ok()?)?;
+        Some(Vec3::from_array(v.to_array()))
+    };
+
+    let mut edges: HashMap<_, (Vec3, Vec3, Vec<Vec3>)> = HashMap::new();
+    for face in geometry.indices.chunks_exact(3) {
+        let (Some(a), Some(b), Some(c)) = (vertex(face[0]), vertex(face[1]), vertex(face[2]))
+        else {
+            continue;
+        };
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        for (start, end) in [(a, b), (b, c), (c, a)] {
+            let (start, end) = if key(start) <= key(end) {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            edges
+                .entry((key(start), key(end)))
+                .or_insert_with(|| (start, end, vec![]))
+                .2
+                .push(normal);
+        }
+    }
+
+    let positions: Vec<[f32; 3]> = edges
+        .into_values()
+        .filter(|(_, _, normals)| match normals[..] {
+            [a, b] => a.angle_between(b) > CREASE_ANGLE,
+            _ => true,
+        })
+        .flat_map(|(start, end, _)| [start.to_array(), end.to_array()])
+        .collect();
+
+    Mesh::new(
+        PrimitiveTopology::LineList,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+}
+
+fn spawn_outline_toggle(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+    show: Res<ShowOutline>,
+) {
+    let Ok(panel) = q_panel.get_single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|children| {
+        children
+            .small_button(toggle_text("Level Outline", show.0))
+            .insert(OutlineToggle)
+            .observe(toggle_outline);
+    });
+}
+
+fn toggle_outline(_trigger: Trigger<OnPress>, mut show: ResMut<ShowOutline>) {
+    show.0 = !show.0;
+}
+
+fn apply_outline_visibility(
+    show: Res<ShowOutline>,
+    mut q_outlines: Query<&mut Visibility, With<LevelOutline>>,
+    q_toggle: Query<&Children, With<OutlineToggle>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for mut visibility in &mut q_outlines {
+        *visibility = if show.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    for children in &q_toggle {
+        set_button_text(children, &mut q_text, toggle_text("Level Outline", show.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn skips_faces_with_missing_vertices() {
+        let geometry = Geometry {
+            dimension: 0,
+            vertices: vec![Vec3::ONE, Vec3::ONE + Vec3::X, Vec3::ONE + Vec3::Y],
+            indices: vec![0, 1, 2, 0, 1, 99],
+        };
+
+        let mesh = create_level_geometry_mesh(&geometry, (1., 2.));
+        assert_eq!(mesh.count_vertices(), 3);
+
+        // The edges of the one triangle, none to the origin from the other.
+        let outline = create_level_outline_mesh(&geometry);
+        assert_eq!(outline.count_vertices(), 6);
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            outline.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("expected outline positions");
+        };
+        assert!(!positions.contains(&[0.; 3]));
+    }
+}
@@ -1,24 +1,36 @@
 use std::f32::consts::PI;
 
+mod bulkhead_links;
+mod camera;
 #[cfg(feature = "dev")]
 mod dev_tools;
+mod dimensions;
+mod doors;
+mod dynamics;
+mod enemies;
+mod follow;
+mod level;
+mod map_objects;
+mod open;
+mod playback;
+mod players;
+mod replay;
+mod resource_containers;
+mod settings;
+mod theme;
+mod timeline;
 
 #[cfg(not(target_arch = "wasm32"))]
 use bevy::pbr::wireframe::{WireframeConfig, WireframePlugin};
 use bevy::{
     audio::{AudioPlugin, Volume},
-    color::palettes::css::CADET_BLUE,
     prelude::*,
-    render::{
-        mesh::{Indices, PrimitiveTopology},
-        render_asset::RenderAssetUsages,
-    },
 };
-use rrv_core::prelude::{parse_replay, Geometry};
 
 fn main() {
     let mut app = App::new();
 
+    replay::register_replay_source(&mut app);
     app.add_plugins((
         DefaultPlugins
             .set(AssetPlugin {
@@ -44,6 +56,25 @@ fn main() {
             }),
         #[cfg(not(target_arch = "wasm32"))]
         WireframePlugin,
+        theme::plugin,
+        replay::plugin,
+        camera::plugin,
+        follow::plugin,
+        dynamics::plugin,
+        players::plugin,
+        enemies::plugin,
+        open::plugin,
+        playback::plugin,
+        timeline::plugin,
+        settings::plugin,
+        dimensions::plugin,
+        (
+            level::plugin,
+            doors::plugin,
+            map_objects::plugin,
+            bulkhead_links::plugin,
+            resource_containers::plugin,
+        ),
     ))
     .add_systems(Startup, setup)
     .add_systems(
@@ -60,67 +91,24 @@ fn main() {
     app.run();
 }
 
-fn setup(
-    mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut ambient_light: ResMut<AmbientLight>,
-) {
+fn setup(mut commands: Commands, mut ambient_light: ResMut<AmbientLight>) {
     ambient_light.brightness = light_consts::lux::OVERCAST_DAY;
 
-    let level_geometry_mat = materials.add(StandardMaterial {
-        base_color: Color::from(CADET_BLUE),
-        double_sided: true,
-        cull_mode: None,
-        ..default()
-    });
-
-    let (_, replay) = parse_replay(include_bytes!("../data/R8E2 2024-08-09 19-55")).unwrap();
-
-    for geometry in replay.header.level_geometry {
-        let shape: Handle<Mesh> = meshes.add(create_level_geometry_mesh(&geometry));
-        commands.spawn((PbrBundle {
-            mesh: shape,
-            material: level_geometry_mat.clone(),
+    // Lights walls facing different ways differently, so rooms read from above.
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: light_consts::lux::OVERCAST_DAY,
             ..default()
-        },));
-    }
-
-    // commands.spawn(DirectionalLightBundle {
-    //     directional_light: DirectionalLight {
-    //         illuminance: light_consts::lux::DIRECT_SUNLIGHT,
-    //         ..default()
-    //     },
-    //     transform: Transform {
-    //         translation: Vec3::new(0., 20., 0.),
-    //         rotation: Quat::from_rotation_x(-PI / 4.),
-    //         ..default()
-    //     },
-    //     ..default()
-    // });
-
-    commands.spawn(Camera3dBundle {
-        transform: Transform::from_xyz(0.0, 7., 14.0).looking_at(Vec3::new(0., 1., 0.), Vec3::Y),
+        },
+        transform: Transform {
+            translation: Vec3::new(0., 20., 0.),
+            rotation: Quat::from_euler(EulerRot::YXZ, PI / 6., -PI / 3., 0.),
+            ..default()
+        },
         ..default()
     });
 }
 
-fn create_level_geometry_mesh(geometry: &Geometry) -> Mesh {
-    Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
-    )
-    .with_inserted_attribute(
-        Mesh::ATTRIBUTE_POSITION,
-        geometry
-            .vertices
-            .iter()
-            .map(|v| v.to_array())
-            .collect::<Vec<[f32; 3]>>(),
-    )
-    .with_inserted_indices(Indices::U16(geometry.indices.clone()))
-}
-
 #[cfg(not(target_arch = "wasm32"))]
 fn toggle_wireframe(
     mut wireframe_config: ResMut<WireframeConfig>,
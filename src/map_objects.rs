@@ -0,0 +1,280 @@
+//! Static objects placed on the map: terminals, generators, disinfect stations,
+//! bulkhead controllers, ladders and spitters.
+
+use bevy::{
+    color::palettes::css::{DARK_GRAY, DARK_ORANGE, GOLD, LIGHT_CYAN, MEDIUM_ORCHID, SILVER, TEAL},
+    prelude::*,
+};
+use rrv_core::prelude::Common;
+
+use crate::{
+    dimensions::Dimension,
+    replay::{
+        common_transform, CurrentReplay, ReplayAsset, ReplayEntity, ReplayLoaded, SpawnReplay,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<MapObject>()
+        .register_type::<Spitter>()
+        .add_systems(
+            Update,
+            (spawn_map_objects, spawn_ladders, spawn_spitters)
+                .in_set(SpawnReplay)
+                .run_if(on_event::<ReplayLoaded>()),
+        );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum MapObjectKind {
+    Terminal,
+    Generator,
+    DisinfectStation,
+    BulkheadController,
+}
+
+/// An object from the replay header. Terminals are recorded without a serial.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct MapObject {
+    pub kind: MapObjectKind,
+    pub id: i32,
+    pub serial: Option<u16>,
+}
+
+#[derive(Component, Debug)]
+pub struct Ladder;
+
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Spitter {
+    pub id: i32,
+}
+
+/// Placeholder model of a kind of map object, standing on the floor.
+struct Model {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    height: f32,
+}
+
+impl Model {
+    fn new(
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        mesh: impl Into<Mesh>,
+        height: f32,
+        color: impl Into<Color>,
+    ) -> Self {
+        Self {
+            mesh: meshes.add(mesh),
+            material: materials.add(color.into()),
+            height,
+        }
+    }
+}
+
+fn spawn_map_objects(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+    let header = &replay.header;
+
+    let terminal = Model::new(
+        &mut meshes,
+        &mut materials,
+        Cuboid::new(0.6, 1.2, 0.5),
+        1.2,
+        DARK_GRAY,
+    );
+    let screen_mesh = meshes.add(Cuboid::new(0.5, 0.35, 0.05));
+    let screen_mat = materials.add(StandardMaterial {
+        base_color: LIGHT_CYAN.into(),
+        emissive: LinearRgba::from(LIGHT_CYAN),
+        ..default()
+    });
+    let generator = Model::new(
+        &mut meshes,
+        &mut materials,
+        Cylinder::new(0.5, 1.2),
+        1.2,
+        GOLD,
+    );
+    let disinfect_station = Model::new(
+        &mut meshes,
+        &mut materials,
+        Cuboid::new(0.8, 2., 0.4),
+        2.,
+        TEAL,
+    );
+    let bulkhead_controller = Model::new(
+        &mut meshes,
+        &mut materials,
+        Cuboid::new(1., 1.5, 0.4),
+        1.5,
+        DARK_ORANGE,
+    );
+
+    let mut spawn = |common: &Common, object: MapObject, model: &Model| {
+        let name = match object.serial {
+            Some(serial) => format!("{:?} {serial}", object.kind),
+            None => format!("{:?} {}", object.kind, object.id),
+        };
+        commands
+            .spawn((
+                Name::new(name),
+                SpatialBundle::from_transform(common_transform(common)),
+                Dimension(common.dimension),
+                ReplayEntity,
+            ))
+            .with_children(|children| {
+                children.spawn(PbrBundle {
+                    mesh: model.mesh.clone(),
+                    material: model.material.clone(),
+                    transform: Transform::from_xyz(0., model.height / 2., 0.),
+                    ..default()
+                });
+                if object.kind == MapObjectKind::Terminal {
+                    children.spawn(PbrBundle {
+                        mesh: screen_mesh.clone(),
+                        material: screen_mat.clone(),
+                        transform: Transform::from_xyz(0., 1., 0.25),
+                        ..default()
+                    });
+                }
+            })
+            .insert(object);
+    };
+
+    let commons = &header.commons;
+    for item in &header.terminals {
+        let Some(common) = commons.get(item.idx) else {
+            continue;
+        };
+        let object = MapObject {
+            kind: MapObjectKind::Terminal,
+            id: item.id,
+            serial: None,
+        };
+        spawn(common, object, &terminal);
+    }
+    for item in &header.generators {
+        let Some(common) = commons.get(item.idx) else {
+            continue;
+        };
+        let object = MapObject {
+            kind: MapObjectKind::Generator,
+            id: item.id,
+            serial: Some(item.serial),
+        };
+        spawn(common, object, &generator);
+    }
+    for item in &header.disinfect_stations {
+        let Some(common) = commons.get(item.idx) else {
+            continue;
+        };
+        let object = MapObject {
+            kind: MapObjectKind::DisinfectStation,
+            id: item.id,
+            serial: Some(item.serial),
+        };
+        spawn(common, object, &disinfect_station);
+    }
+    for item in &header.bulkhead_controllers {
+        let Some(common) = commons.get(item.idx) else {
+            continue;
+        };
+        let object = MapObject {
+            kind: MapObjectKind::BulkheadController,
+            id: item.id,
+            serial: Some(item.serial),
+        };
+        spawn(common, object, &bulkhead_controller);
+    }
+}
+
+fn spawn_ladders(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    // A ladder of height 1, stretched to the height of each ladder.
+    let mesh = meshes.add(Cuboid::new(0.6, 1., 0.1));
+    let material = materials.add(Color::from(SILVER));
+
+    for ladder in &replay.header.ladders {
+        let Some(common) = replay.header.commons.get(ladder.idx) else {
+            continue;
+        };
+        let height = ladder.height as f32;
+        commands
+            .spawn((
+                Name::new("Ladder"),
+                SpatialBundle::from_transform(common_transform(common)),
+                Ladder,
+                Dimension(common.dimension),
+                ReplayEntity,
+            ))
+            .with_children(|children| {
+                children.spawn(PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_xyz(0., height / 2., 0.)
+                        .with_scale(Vec3::new(1., height, 1.)),
+                    ..default()
+                });
+            });
+    }
+}
+
+fn spawn_spitters(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    // Spitters grow out of walls and ceilings, flattened against the surface
+    // they sit on, which faces along their local z axis.
+    let mesh = meshes.add(Sphere::new(0.5));
+    let material = materials.add(StandardMaterial {
+        base_color: MEDIUM_ORCHID.into(),
+        emissive: LinearRgba::from(MEDIUM_ORCHID) * 0.3,
+        ..default()
+    });
+
+    for spitter in &replay.header.spitters {
+        let Some(common) = replay.header.commons.get(spitter.idx) else {
+            continue;
+        };
+        let scale = spitter.scale as f32;
+        commands.spawn((
+            Name::new(format!("Spitter {}", spitter.id)),
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: common_transform(common).with_scale(Vec3::new(1., 1., 0.4) * scale),
+                ..default()
+            },
+            Spitter { id: spitter.id },
+            Dimension(common.dimension),
+            ReplayEntity,
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+//! Picking the replay to show, through the open dialog or by dropping a file
+//! onto the window.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use bevy::{prelude::*, tasks::IoTaskPool, ui::Val::*};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    replay::{CurrentReplay, ReplayAsset},
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<OpenReplay>()
+        .add_systems(Startup, spawn_open_button)
+        .add_systems(
+            Update,
+            (
+                #[cfg(not(target_arch = "wasm32"))]
+                open_dropped_file.run_if(on_event::<FileDragAndDrop>()),
+                send_picked_replays,
+                open_replay.run_if(on_event::<OpenReplay>()),
+            )
+                .chain(),
+        );
+}
+
+/// Sent to replace the current replay.
+#[derive(Event)]
+pub enum OpenReplay {
+    /// A file loaded through the asset server. Only files under `assets/` are
+    /// watched and reloaded when they change on disk.
+    #[cfg(not(target_arch = "wasm32"))]
+    Path(PathBuf),
+    /// The contents of a file handed over by the browser.
+    #[cfg(target_arch = "wasm32")]
+    Bytes(Vec<u8>),
+}
+
+/// Replays picked outside of the schedule, by the open dialog or the drop
+/// handler in `web/index.html`.
+static PICKED: Mutex<Vec<OpenReplay>> = Mutex::new(Vec::new());
+
+fn spawn_open_button(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Open Replay"),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Px(10.0),
+                    left: Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|children| {
+            children.button("Open replay").observe(open_dialog);
+        });
+}
+
+fn open_dialog(_trigger: Trigger<OnPress>) {
+    IoTaskPool::get()
+        .spawn(async {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_title("Open replay")
+                .pick_file()
+                .await
+            else {
+                return;
+            };
+
+            #[cfg(not(target_arch = "wasm32"))]
+            let replay = OpenReplay::Path(file.path().to_owned());
+            #[cfg(target_arch = "wasm32")]
+            let replay = OpenReplay::Bytes(file.read().await);
+
+            PICKED.lock().unwrap().push(replay);
+        })
+        .detach();
+}
+
+/// Called from `web/index.html` with the contents of a file dropped onto the canvas.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn open_replay_bytes(bytes: Vec<u8>) {
+    PICKED.lock().unwrap().push(OpenReplay::Bytes(bytes));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn open_dropped_file(
+    mut drop_events: EventReader<FileDragAndDrop>,
+    mut open: EventWriter<OpenReplay>,
+) {
+    for event in drop_events.read() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = event {
+            open.send(OpenReplay::Path(path_buf.clone()));
+        }
+    }
+}
+
+fn send_picked_replays(mut open: EventWriter<OpenReplay>) {
+    open.send_batch(PICKED.lock().unwrap().drain(..));
+}
+
+fn open_replay(
+    mut commands: Commands,
+    mut open: EventReader<OpenReplay>,
+    #[cfg(not(target_arch = "wasm32"))] asset_server: Res<AssetServer>,
+    #[cfg(target_arch = "wasm32")] mut replays: ResMut<Assets<ReplayAsset>>,
+) {
+    // Only the last one stays open when several files are dropped at once.
+    let Some(event) = open.read().last() else {
+        return;
+    };
+
+    let handle = match event {
+        #[cfg(not(target_arch = "wasm32"))]
+        OpenReplay::Path(path) => asset_server.load::<ReplayAsset>(path.clone()),
+        #[cfg(target_arch = "wasm32")]
+        OpenReplay::Bytes(bytes) => match crate::replay::parse_replay_bytes(bytes) {
+            Ok(replay) => replays.add(ReplayAsset(replay)),
+            Err(e) => {
+                error!("could not parse replay: {e}");
+                return;
+            }
+        },
+    };
+    commands.insert_resource(CurrentReplay(handle));
+}
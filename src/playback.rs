@@ -0,0 +1,469 @@
+//! Playing back the snapshots of the current replay.
+
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+use rrv_core::prelude::{
+    Common, DoorState, DynamicData, EnemyBehaviour, EventData, PlayerBackpack, PlayerStats,
+    Snapshot, SpawnInfo,
+};
+
+use crate::replay::{CurrentReplay, ReplayAsset, ReplayLoaded, SpawnReplay};
+
+/// Replay time between two keyframes.
+const KEYFRAME_INTERVAL: u32 = 10_000;
+
+/// How far the arrow keys seek.
+const SEEK_STEP: Duration = Duration::from_secs(5);
+
+/// Entities moving faster than this between two snapshots, in meters per
+/// second, teleported. Well above how fast players and enemies run.
+const TELEPORT_SPEED: f32 = 25.;
+
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 16.;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PlaybackState>()
+        .init_resource::<ReplayWorld>()
+        .init_resource::<Keyframes>()
+        .init_resource::<Interpolated>()
+        .add_systems(
+            Update,
+            (
+                reset_playback
+                    .in_set(SpawnReplay)
+                    .run_if(on_event::<ReplayLoaded>()),
+                (
+                    playback_controls,
+                    advance_playback,
+                    seek_world,
+                    interpolate_world,
+                )
+                    .chain()
+                    .in_set(ApplySnapshots)
+                    .after(SpawnReplay),
+            ),
+        );
+}
+
+/// Systems bringing [`ReplayWorld`] to the current [`PlaybackState::time`].
+/// Systems reading the world state should run after this set.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApplySnapshots;
+
+#[derive(Resource, Debug)]
+pub struct PlaybackState {
+    /// Time since the start of the replay.
+    pub time: Duration,
+    /// Length of the replay, the timestamp of its last snapshot.
+    pub duration: Duration,
+    /// Multiplier for how fast replay time passes.
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl Default for PlaybackState {
+    fn default() -> Self {
+        Self {
+            time: Duration::ZERO,
+            duration: Duration::ZERO,
+            speed: 1.,
+            paused: true,
+        }
+    }
+}
+
+impl PlaybackState {
+    pub fn toggle(&mut self) {
+        // Playing from the end starts over.
+        if self.paused && self.time >= self.duration {
+            self.time = Duration::ZERO;
+        }
+        self.paused = !self.paused;
+    }
+
+    pub fn seek(&mut self, time: Duration) {
+        self.time = time.min(self.duration);
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// [`Self::time`] in replay timestamps.
+    pub fn timestamp(&self) -> u32 {
+        self.time.as_millis().try_into().unwrap_or(u32::MAX)
+    }
+}
+
+/// State of the replay at some point in time, built by applying snapshots in order.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ReplayWorld {
+    /// Number of snapshots applied.
+    pub applied: usize,
+    /// Transforms of dynamic entities by type id and id, as ids are only unique
+    /// within a type.
+    pub entities: HashMap<(u16, i32), Common>,
+    pub doors: HashMap<i32, DoorState>,
+    /// Health and infection of players by id.
+    pub player_stats: HashMap<i32, PlayerStats>,
+    pub backpacks: HashMap<i32, PlayerBackpack>,
+    pub enemies: HashMap<i32, EnemyState>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnemyState {
+    pub health: f32,
+    /// Id of the player the enemy is after.
+    pub target: Option<i32>,
+    pub behaviour: EnemyBehaviour,
+}
+
+/// Transforms of the dynamic entities at [`PlaybackState::time`], between the
+/// snapshots around it.
+#[derive(Resource, Debug, Default)]
+pub struct Interpolated(pub HashMap<(u16, i32), Common>);
+
+impl ReplayWorld {
+    fn apply(&mut self, snapshot: &Snapshot) {
+        for event in &snapshot.events {
+            match event.data {
+                EventData::Spawn {
+                    type_id,
+                    id,
+                    common,
+                    ref info,
+                } => {
+                    self.entities.insert((type_id, id), common);
+                    if let Some(SpawnInfo::Enemy(enemy)) = info {
+                        self.enemies.insert(
+                            id,
+                            EnemyState {
+                                health: enemy.max_health,
+                                ..default()
+                            },
+                        );
+                    }
+                }
+                EventData::Despawn { type_id, id } => {
+                    self.entities.remove(&(type_id, id));
+                    self.player_stats.remove(&id);
+                    self.backpacks.remove(&id);
+                    self.enemies.remove(&id);
+                }
+                EventData::DoorStatus { id, state } => {
+                    self.doors.insert(id, state);
+                }
+                EventData::EnemyHealth { id, health } => {
+                    self.enemies.entry(id).or_default().health = health;
+                }
+                EventData::EnemyTarget { id, target } => {
+                    self.enemies.entry(id).or_default().target = target;
+                }
+                EventData::EnemyBehaviour { id, behaviour } => {
+                    self.enemies.entry(id).or_default().behaviour = behaviour;
+                }
+                // Decoded by other crates, the viewer doesn't show them.
+                EventData::Extension(_) => {}
+            }
+        }
+        for dynamic in &snapshot.dynamics {
+            match &dynamic.data {
+                DynamicData::Transform(common) => {
+                    self.entities.insert((dynamic.type_id, dynamic.id), *common);
+                }
+                DynamicData::PlayerStats(stats) => {
+                    self.player_stats.insert(dynamic.id, *stats);
+                }
+                DynamicData::PlayerBackpack(backpack) => {
+                    self.backpacks.insert(dynamic.id, backpack.clone());
+                }
+                DynamicData::Extension(_) => {}
+            }
+        }
+        self.applied += 1;
+    }
+
+    /// Transforms of the entities at `timestamp`, part of the way to where the
+    /// next snapshot puts them.
+    pub fn interpolate(
+        &self,
+        snapshots: &[Snapshot],
+        timestamp: u32,
+    ) -> HashMap<(u16, i32), Common> {
+        let Some(next) = snapshots.get(self.applied) else {
+            return self.entities.clone();
+        };
+        let from = self
+            .applied
+            .checked_sub(1)
+            .and_then(|i| snapshots.get(i))
+            .map_or(0, |s| s.timestamp);
+        let span = next.timestamp.saturating_sub(from).max(1);
+        let t = (timestamp.saturating_sub(from) as f32 / span as f32).min(1.);
+        let seconds = span as f32 / 1000.;
+
+        let targets: HashMap<(u16, i32), Common> = next
+            .dynamics
+            .iter()
+            .filter_map(|dynamic| match dynamic.data {
+                DynamicData::Transform(common) => Some(((dynamic.type_id, dynamic.id), common)),
+                _ => None,
+            })
+            .collect();
+        self.entities
+            .iter()
+            .map(|(&key, common)| match targets.get(&key) {
+                Some(target) => (key, interpolate(common, target, seconds, t)),
+                None => (key, *common),
+            })
+            .collect()
+    }
+
+    /// Brings the world to `timestamp`, starting from the closest keyframe when
+    /// that is ahead of the current state or the target lies in the past.
+    pub fn seek(&mut self, keyframes: &Keyframes, snapshots: &[Snapshot], timestamp: u32) {
+        let target = snapshots.partition_point(|s| s.timestamp <= timestamp);
+        let k = keyframes.0.partition_point(|k| k.applied <= target);
+        match keyframes.0[..k].last() {
+            Some(keyframe) if target < self.applied || keyframe.applied > self.applied => {
+                self.clone_from(keyframe);
+            }
+            None if target < self.applied => *self = Self::default(),
+            _ => {}
+        }
+        for snapshot in &snapshots[self.applied..target] {
+            self.apply(snapshot);
+        }
+    }
+}
+
+/// Transform `t` of the way from `from` to `to`, which are `seconds` apart.
+/// Entities changing dimension or teleporting stay put until the snapshot
+/// moving them is reached, instead of sliding across the level.
+pub fn interpolate(from: &Common, to: &Common, seconds: f32, t: f32) -> Common {
+    let speed = from.position.distance(to.position) / seconds;
+    if from.dimension != to.dimension || speed > TELEPORT_SPEED {
+        return *from;
+    }
+    Common {
+        dimension: from.dimension,
+        position: from.position.lerp(to.position, t),
+        rotation: from.rotation.slerp(to.rotation, f64::from(t)),
+    }
+}
+
+/// World states saved every [`KEYFRAME_INTERVAL`] of replay time, so seeking
+/// doesn't have to apply every snapshot from the start.
+#[derive(Resource, Debug, Default)]
+pub struct Keyframes(Vec<ReplayWorld>);
+
+impl Keyframes {
+    pub fn build(snapshots: &[Snapshot]) -> Self {
+        let mut keyframes = vec![ReplayWorld::default()];
+        let mut world = ReplayWorld::default();
+        let mut next = KEYFRAME_INTERVAL;
+        for snapshot in snapshots {
+            if snapshot.timestamp >= next {
+                keyframes.push(world.clone());
+                next = snapshot.timestamp.saturating_add(KEYFRAME_INTERVAL);
+            }
+            world.apply(snapshot);
+        }
+
+        Self(keyframes)
+    }
+}
+
+fn reset_playback(
+    mut commands: Commands,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    let duration = replay.snapshots.last().map_or(0, |s| s.timestamp);
+    commands.insert_resource(PlaybackState {
+        duration: Duration::from_millis(duration.into()),
+        ..default()
+    });
+    commands.insert_resource(ReplayWorld::default());
+    commands.insert_resource(Interpolated::default());
+    commands.insert_resource(Keyframes::build(&replay.snapshots));
+}
+
+fn playback_controls(mut playback: ResMut<PlaybackState>, input: Res<ButtonInput<KeyCode>>) {
+    if input.just_pressed(KeyCode::Space) {
+        playback.toggle();
+    }
+    if input.just_pressed(KeyCode::ArrowRight) {
+        let time = playback.time.saturating_add(SEEK_STEP);
+        playback.seek(time);
+    }
+    if input.just_pressed(KeyCode::ArrowLeft) {
+        let time = playback.time.saturating_sub(SEEK_STEP);
+        playback.seek(time);
+    }
+    if input.just_pressed(KeyCode::ArrowUp) {
+        let speed = playback.speed * 2.;
+        playback.set_speed(speed);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        let speed = playback.speed / 2.;
+        playback.set_speed(speed);
+    }
+}
+
+fn advance_playback(mut playback: ResMut<PlaybackState>, time: Res<Time>) {
+    if playback.paused {
+        return;
+    }
+
+    let time = playback.time + time.delta().mul_f32(playback.speed);
+    playback.seek(time);
+    if playback.time >= playback.duration {
+        playback.paused = true;
+    }
+}
+
+fn seek_world(
+    playback: Res<PlaybackState>,
+    mut world: ResMut<ReplayWorld>,
+    keyframes: Res<Keyframes>,
+    current: Option<Res<CurrentReplay>>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    if !playback.is_changed() {
+        return;
+    }
+    let Some(replay) = current.and_then(|current| replays.get(&current.0)) else {
+        return;
+    };
+
+    world.seek(&keyframes, &replay.snapshots, playback.timestamp());
+}
+
+fn interpolate_world(
+    playback: Res<PlaybackState>,
+    world: Res<ReplayWorld>,
+    mut interpolated: ResMut<Interpolated>,
+    current: Option<Res<CurrentReplay>>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    if !playback.is_changed() {
+        return;
+    }
+    let Some(replay) = current.and_then(|current| replays.get(&current.0)) else {
+        return;
+    };
+
+    interpolated.0 = world.interpolate(&replay.snapshots, playback.timestamp());
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{DQuat, Vec3};
+    use rrv_core::prelude::Dynamic;
+
+    use super::*;
+
+    fn snapshot(timestamp: u32, x: f32) -> Snapshot {
+        Snapshot {
+            timestamp,
+            events: vec![],
+            dynamics: vec![Dynamic {
+                type_id: 0,
+                id: 1,
+                data: DynamicData::Transform(Common {
+                    dimension: 0,
+                    position: Vec3::new(x, 0., 0.),
+                    rotation: DQuat::IDENTITY,
+                }),
+            }],
+            undecoded: None,
+        }
+    }
+
+    #[test]
+    fn interpolates_towards_next_snapshot() {
+        let snapshots: Vec<_> = (0..10).map(|i| snapshot(i * 500, i as f32)).collect();
+        let keyframes = Keyframes::build(&snapshots);
+
+        let mut world = ReplayWorld::default();
+        world.seek(&keyframes, &snapshots, 1_125);
+        assert_eq!(
+            world.interpolate(&snapshots, 1_125)[&(0, 1)].position.x,
+            2.25
+        );
+
+        world.seek(&keyframes, &snapshots, 9_000);
+        assert_eq!(world.interpolate(&snapshots, 9_000)[&(0, 1)].position.x, 9.);
+    }
+
+    #[test]
+    fn slerps_rotation_and_jumps_on_teleport() {
+        let common = |dimension, x, angle| Common {
+            dimension,
+            position: Vec3::new(x, 0., 0.),
+            rotation: DQuat::from_rotation_y(angle),
+        };
+
+        let halfway = interpolate(&common(0, 0., 0.), &common(0, 1., 1.), 1., 0.5);
+        assert_eq!(halfway.position.x, 0.5);
+        assert!(halfway
+            .rotation
+            .abs_diff_eq(DQuat::from_rotation_y(0.5), 1e-9));
+
+        let teleport = interpolate(&common(0, 0., 0.), &common(0, 50., 0.), 1., 0.5);
+        assert_eq!(teleport.position.x, 0.);
+        // As far, but over a long gap between snapshots.
+        let running = interpolate(&common(0, 0., 0.), &common(0, 50., 0.), 10., 0.5);
+        assert_eq!(running.position.x, 25.);
+        let dimension_change = interpolate(&common(0, 0., 0.), &common(1, 1., 0.), 1., 0.5);
+        assert_eq!(
+            (dimension_change.dimension, dimension_change.position.x),
+            (0, 0.)
+        );
+    }
+
+    #[test]
+    fn keeps_entities_of_different_types_with_the_same_id() {
+        let dynamic = |type_id, x| Dynamic {
+            type_id,
+            id: 1,
+            data: DynamicData::Transform(Common {
+                dimension: 0,
+                position: Vec3::new(x, 0., 0.),
+                rotation: DQuat::IDENTITY,
+            }),
+        };
+        let snapshot = Snapshot {
+            timestamp: 0,
+            events: vec![],
+            dynamics: vec![dynamic(3, 1.), dynamic(4, 2.)],
+            undecoded: None,
+        };
+
+        let mut world = ReplayWorld::default();
+        world.apply(&snapshot);
+        assert_eq!(world.entities[&(3, 1)].position.x, 1.);
+        assert_eq!(world.entities[&(4, 1)].position.x, 2.);
+    }
+
+    #[test]
+    fn seeking_backward_matches_playing_forward() {
+        let snapshots: Vec<_> = (0..100).map(|i| snapshot(i * 500, i as f32)).collect();
+        let keyframes = Keyframes::build(&snapshots);
+        assert!(keyframes.0.len() > 1);
+
+        let mut world = ReplayWorld::default();
+        world.seek(&keyframes, &snapshots, 40_000);
+        world.seek(&keyframes, &snapshots, 12_300);
+
+        assert_eq!(world.applied, 25);
+        assert_eq!(world.entities[&(0, 1)].position.x, 24.);
+    }
+}
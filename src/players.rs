@@ -0,0 +1,220 @@
+//! Player avatars, with their name above them.
+
+use bevy::{
+    prelude::*,
+    render::view::VisibilitySystems,
+    transform::TransformSystem,
+    utils::{HashMap, HashSet},
+};
+use rrv_core::prelude::{EventData, SpawnInfo};
+
+use crate::{
+    dynamics::DynamicEntity,
+    replay::{CurrentReplay, ReplayAsset, ReplayEntity, ReplayLoaded, SpawnReplay},
+    theme::palette::PLAYER_COLORS,
+};
+
+/// Height of the name labels above the feet of the avatars.
+const LABEL_HEIGHT: f32 = 2.2;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Players>()
+        .add_systems(
+            Update,
+            (find_players, spawn_avatars)
+                .chain()
+                .in_set(SpawnReplay)
+                .run_if(on_event::<ReplayLoaded>()),
+        )
+        .add_systems(
+            PostUpdate,
+            place_name_labels
+                .after(TransformSystem::TransformPropagate)
+                .after(VisibilitySystems::VisibilityPropagate),
+        );
+}
+
+#[derive(Debug, Clone)]
+pub struct Player {
+    /// Type of the player in the typemap.
+    pub type_id: u16,
+    pub id: i32,
+    /// Lobby slot, from 0 to 3. Other players may take the slot after this one left.
+    pub slot: u8,
+    pub name: String,
+}
+
+/// Players of the current replay, by slot.
+#[derive(Resource, Debug, Default)]
+pub struct Players(pub Vec<Player>);
+
+/// UI text naming the player of an avatar, kept above it on screen.
+#[derive(Component, Debug)]
+struct NameLabel(Entity);
+
+fn find_players(
+    mut players: ResMut<Players>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+) {
+    players.0.clear();
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    let mut named: HashMap<i32, Player> = HashMap::new();
+    let mut unnamed = vec![];
+    let mut seen = HashSet::new();
+    for snapshot in &replay.snapshots {
+        for event in &snapshot.events {
+            if let EventData::Spawn {
+                type_id,
+                id,
+                info: Some(SpawnInfo::Player(ref info)),
+                ..
+            } = event.data
+            {
+                named.entry(id).or_insert_with(|| Player {
+                    type_id,
+                    id,
+                    slot: info.slot,
+                    name: info.name.clone(),
+                });
+            }
+        }
+        // Recorders before `Vanilla.Player` 0.0.2 only record the transform.
+        for dynamic in &snapshot.dynamics {
+            let is_player = replay
+                .typemap
+                .get(dynamic.type_id)
+                .is_some_and(|t| t.typename == "Vanilla.Player");
+            if is_player && seen.insert(dynamic.id) {
+                unnamed.push((dynamic.type_id, dynamic.id));
+            }
+        }
+    }
+
+    let mut taken: HashSet<u8> = named.values().map(|player| player.slot).collect();
+    let mut slot = 0;
+    for (type_id, id) in unnamed {
+        if named.contains_key(&id) {
+            continue;
+        }
+        while !taken.insert(slot) {
+            slot += 1;
+        }
+        named.insert(
+            id,
+            Player {
+                type_id,
+                id,
+                slot,
+                name: format!("Player {}", slot + 1),
+            },
+        );
+    }
+    players.0 = named.into_values().collect();
+    players.0.sort_by_key(|player| (player.slot, player.id));
+}
+
+/// Color of the players in a slot.
+pub fn player_color(slot: u8) -> Color {
+    PLAYER_COLORS[usize::from(slot) % PLAYER_COLORS.len()]
+}
+
+fn spawn_avatars(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    players: Res<Players>,
+) {
+    let body_mesh = meshes.add(Capsule3d::new(0.3, 1.2));
+    let visor_mesh = meshes.add(Cuboid::new(0.4, 0.15, 0.1));
+    let visor_mat = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        emissive: LinearRgba::WHITE * 0.5,
+        ..default()
+    });
+
+    for player in &players.0 {
+        let color = player_color(player.slot);
+        let avatar = commands
+            .spawn((
+                Name::new(player.name.clone()),
+                SpatialBundle {
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                DynamicEntity {
+                    type_id: player.type_id,
+                    id: player.id,
+                },
+                ReplayEntity,
+            ))
+            .with_children(|children| {
+                children.spawn(PbrBundle {
+                    mesh: body_mesh.clone(),
+                    material: materials.add(color),
+                    transform: Transform::from_xyz(0., 0.9, 0.),
+                    ..default()
+                });
+                // Shows which way the player looks, forward along -z.
+                children.spawn(PbrBundle {
+                    mesh: visor_mesh.clone(),
+                    material: visor_mat.clone(),
+                    transform: Transform::from_xyz(0., 1.55, -0.28),
+                    ..default()
+                });
+            })
+            .id();
+
+        commands.spawn((
+            Name::new(format!("{} Label", player.name)),
+            NameLabel(avatar),
+            ReplayEntity,
+            TextBundle::from_section(
+                player.name.clone(),
+                TextStyle {
+                    font_size: 18.0,
+                    color,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            }),
+        ));
+    }
+}
+
+fn place_name_labels(
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    q_avatars: Query<(&GlobalTransform, &InheritedVisibility), With<DynamicEntity>>,
+    mut q_labels: Query<(&NameLabel, &Node, &mut Style, &mut Visibility)>,
+) {
+    let Some((camera, camera_transform)) = q_camera.iter().find(|(camera, _)| camera.is_active)
+    else {
+        return;
+    };
+
+    for (label, node, mut style, mut visibility) in &mut q_labels {
+        let position = q_avatars
+            .get(label.0)
+            .ok()
+            .filter(|(_, shown)| shown.get())
+            .and_then(|(transform, _)| {
+                let above = transform.translation() + Vec3::Y * LABEL_HEIGHT;
+                camera.world_to_viewport(camera_transform, above)
+            });
+        let Some(position) = position else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        let size = node.size();
+        style.left = Val::Px(position.x - size.x / 2.);
+        style.top = Val::Px(position.y - size.y);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
@@ -0,0 +1,236 @@
+//! Loading replay files through the asset server.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use bevy::{
+    asset::{
+        io::{AssetSource, Reader},
+        AssetLoader, AssetPath, AsyncReadExt, LoadContext,
+    },
+    prelude::*,
+};
+use rrv_core::prelude::{
+    parse_replay_with, set_identifier_database, Common, IdentifierDatabase, ReplayError,
+    ReplayOwned, TypeRegistry,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<ReplayAsset>()
+        .init_asset_loader::<ReplayLoader>()
+        .add_event::<ReplayLoaded>()
+        .add_systems(
+            Startup,
+            (
+                #[cfg(not(target_arch = "wasm32"))]
+                load_identifier_overrides,
+                load_replay_from_args,
+            ),
+        )
+        .add_systems(Update, watch_current_replay.before(SpawnReplay));
+}
+
+/// Asset source rooted at the directory of the replay given on the command
+/// line, so it is watched like the files under `assets/`.
+const ARGS_SOURCE: &str = "args";
+
+/// The replay given as the first command line argument, in [`ARGS_SOURCE`].
+#[derive(Resource, Debug)]
+struct ReplayArg(AssetPath<'static>);
+
+/// A parsed replay file.
+#[derive(Asset, TypePath, Deref)]
+pub struct ReplayAsset(pub ReplayOwned);
+
+/// The replay shown by the viewer.
+#[derive(Resource, Debug)]
+pub struct CurrentReplay(pub Handle<ReplayAsset>);
+
+/// Sent when the current replay finished loading, or was reloaded from disk.
+#[derive(Event, Debug)]
+pub struct ReplayLoaded;
+
+/// Systems spawning entities from the current replay. Runs after entities of
+/// the previous replay were despawned and [`ReplayLoaded`] was sent.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpawnReplay;
+
+/// Marker for entities spawned from the current replay, despawned when it changes.
+#[derive(Component, Debug, Default)]
+pub struct ReplayEntity;
+
+/// Placement of a replay object. The parser uses its own version of glam, so
+/// values go through arrays.
+pub fn common_transform(common: &Common) -> Transform {
+    Transform {
+        translation: Vec3::from_array(common.position.to_array()),
+        rotation: Quat::from_array(common.rotation.as_quat().to_array()),
+        ..default()
+    }
+}
+
+#[derive(Default)]
+pub struct ReplayLoader;
+
+#[derive(Debug)]
+pub enum ReplayLoaderError {
+    Io(std::io::Error),
+    Parse(ReplayError),
+}
+
+impl fmt::Display for ReplayLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read replay: {e}"),
+            Self::Parse(e) => write!(f, "could not parse replay: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayLoaderError {}
+
+impl From<std::io::Error> for ReplayLoaderError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ReplayError> for ReplayLoaderError {
+    fn from(e: ReplayError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl AssetLoader for ReplayLoader {
+    type Asset = ReplayAsset;
+    type Settings = ();
+    type Error = ReplayLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ReplayAsset(parse_replay_bytes(&bytes)?))
+    }
+
+    // `.replay` files are matched by extension. The recorder writes replays
+    // without one, those are loaded by asset type.
+    fn extensions(&self) -> &[&str] {
+        &["replay"]
+    }
+}
+
+/// Parses a replay with the vanilla types, and with the enemy layouts that
+/// are not checked against the recorder yet when the `provisional_enemies`
+/// feature is on.
+pub fn parse_replay_bytes(bytes: &[u8]) -> Result<ReplayOwned, ReplayError> {
+    static REGISTRY: OnceLock<TypeRegistry> = OnceLock::new();
+    let registry = REGISTRY.get_or_init(|| {
+        let mut registry = TypeRegistry::vanilla();
+        if cfg!(feature = "provisional_enemies") {
+            registry.register_provisional_enemies();
+        }
+        registry
+    });
+    Ok(parse_replay_with(bytes, registry)?.into_owned())
+}
+
+/// Registers [`ARGS_SOURCE`] for the replay given as the first command line
+/// argument. Has to run before `AssetPlugin` is added.
+pub fn register_replay_source(app: &mut App) {
+    let Some(path) = std::env::args_os().nth(1).map(PathBuf::from) else {
+        return;
+    };
+    // Relative to where the viewer was started, not to `assets/`.
+    let path = std::path::absolute(&path).unwrap_or(path);
+    let (Some(dir), Some(file)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+    let dir = dir.to_string_lossy().into_owned();
+
+    app.register_asset_source(
+        ARGS_SOURCE,
+        AssetSource::build()
+            .with_reader(AssetSource::get_default_reader(dir.clone()))
+            .with_watcher(AssetSource::get_default_watcher(
+                dir,
+                Duration::from_millis(300),
+            )),
+    )
+    .insert_resource(ReplayArg(
+        AssetPath::from_path(Path::new(file))
+            .into_owned()
+            .with_source(ARGS_SOURCE),
+    ));
+}
+
+/// Loads the replay given as the first command line argument.
+fn load_replay_from_args(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    arg: Option<Res<ReplayArg>>,
+) {
+    let Some(arg) = arg else {
+        info!("No replay given, pass the path to a replay as the first argument");
+        return;
+    };
+    commands.insert_resource(CurrentReplay(asset_server.load(arg.0.clone())));
+}
+
+/// Merges `identifiers.ron` from the working directory over the bundled
+/// identifier names, so they can follow game updates without a new build.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_identifier_overrides() {
+    const PATH: &str = "identifiers.ron";
+    let Ok(overrides) = std::fs::read_to_string(PATH) else {
+        return;
+    };
+    match IdentifierDatabase::from_ron(&overrides) {
+        Ok(overrides) => {
+            let mut database = IdentifierDatabase::bundled();
+            database.merge(overrides);
+            set_identifier_database(database);
+            info!("Loaded identifier names from {PATH}");
+        }
+        Err(e) => error!("Failed to read {PATH}: {e}"),
+    }
+}
+
+fn watch_current_replay(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<ReplayAsset>>,
+    mut loaded: EventWriter<ReplayLoaded>,
+    current: Option<Res<CurrentReplay>>,
+    replays: Res<Assets<ReplayAsset>>,
+    q_replay_entities: Query<Entity, With<ReplayEntity>>,
+) {
+    let Some(current) = current else {
+        asset_events.clear();
+        return;
+    };
+
+    let changed = asset_events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            *id == current.0.id()
+        }
+        _ => false,
+    });
+    // Replays added from memory, or opened again, don't send a load event.
+    let replaced = current.is_changed() && replays.contains(&current.0);
+    if !changed && !replaced {
+        return;
+    }
+
+    for entity in &q_replay_entities {
+        commands.entity(entity).despawn_recursive();
+    }
+    loaded.send(ReplayLoaded);
+}
@@ -0,0 +1,255 @@
+//! Resource boxes and lockers, with their locks and the item inside.
+
+use bevy::{
+    color::palettes::css::{
+        DARK_OLIVEGREEN, GOLD, LIGHT_SKY_BLUE, ORANGE_RED, SILVER, STEEL_BLUE, WHITE,
+    },
+    prelude::*,
+    utils::HashMap,
+};
+use rrv_core::prelude::{IdentifierType, LockType};
+
+use crate::{
+    dimensions::Dimension,
+    replay::{
+        common_transform, CurrentReplay, ReplayAsset, ReplayEntity, ReplayLoaded, SpawnReplay,
+    },
+    settings::{set_button_text, spawn_settings_panel, toggle_text, SettingsPanel},
+    theme::prelude::*,
+};
+
+/// Opacity of container bodies while [`TransparentContainers`] is on.
+const TRANSPARENT_ALPHA: f32 = 0.2;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Container>()
+        .init_resource::<TransparentContainers>()
+        .add_systems(
+            Startup,
+            spawn_transparency_toggle.after(spawn_settings_panel),
+        )
+        .add_systems(
+            Update,
+            (
+                spawn_containers
+                    .in_set(SpawnReplay)
+                    .run_if(on_event::<ReplayLoaded>()),
+                apply_transparency.run_if(resource_changed::<TransparentContainers>),
+            ),
+        );
+}
+
+/// Whether container bodies are see-through, showing the item inside.
+#[derive(Resource, Debug, Default)]
+pub struct TransparentContainers(pub bool);
+
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Container {
+    pub id: i32,
+    pub serial: u16,
+    pub locker: bool,
+}
+
+/// The item inside a container.
+#[derive(Component, Debug)]
+pub struct ContainerItem;
+
+/// Materials of the container bodies, made see-through by [`TransparentContainers`].
+#[derive(Resource, Debug)]
+struct ContainerMaterials(Vec<Handle<StandardMaterial>>);
+
+#[derive(Component, Debug)]
+struct TransparencyToggle;
+
+fn body_material(color: impl Into<Color>, transparent: bool) -> StandardMaterial {
+    let mut material = StandardMaterial::from(color.into());
+    set_transparent(&mut material, transparent);
+    material
+}
+
+/// Color of an item by its category in the identifier database, white when
+/// the database doesn't know it.
+fn item_color(category: Option<&str>) -> Srgba {
+    match category {
+        Some("Resource") => LIGHT_SKY_BLUE,
+        Some("Consumable") => GOLD,
+        _ => WHITE,
+    }
+}
+
+fn set_transparent(material: &mut StandardMaterial, transparent: bool) {
+    if transparent {
+        material.base_color.set_alpha(TRANSPARENT_ALPHA);
+        material.alpha_mode = AlphaMode::Blend;
+    } else {
+        material.base_color.set_alpha(1.);
+        material.alpha_mode = AlphaMode::Opaque;
+    }
+}
+
+fn spawn_containers(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+    transparent: Res<TransparentContainers>,
+) {
+    let Some(replay) = replays.get(&current.0) else {
+        return;
+    };
+
+    let box_size = Vec3::new(0.8, 0.5, 0.5);
+    let locker_size = Vec3::new(1., 2., 0.6);
+    let box_mesh = meshes.add(Cuboid::from_size(box_size));
+    let locker_mesh = meshes.add(Cuboid::from_size(locker_size));
+    let box_mat = materials.add(body_material(DARK_OLIVEGREEN, transparent.0));
+    let locker_mat = materials.add(body_material(STEEL_BLUE, transparent.0));
+    commands.insert_resource(ContainerMaterials(vec![
+        box_mat.clone(),
+        locker_mat.clone(),
+    ]));
+
+    let padlock_mesh = meshes.add(Cuboid::new(0.15, 0.2, 0.08));
+    let padlock_mat = materials.add(Color::from(SILVER));
+    let hacklock_mesh = meshes.add(Cuboid::new(0.25, 0.3, 0.08));
+    let hacklock_mat = materials.add(StandardMaterial {
+        base_color: ORANGE_RED.into(),
+        emissive: LinearRgba::from(ORANGE_RED),
+        ..default()
+    });
+    let item_mesh = meshes.add(Sphere::new(0.12));
+    let mut item_materials = HashMap::new();
+
+    for container in &replay.header.resource_containers {
+        let Some(common) = replay.header.commons.get(container.idx) else {
+            continue;
+        };
+        let (name, size, mesh, material) = if container.locker {
+            ("Locker", locker_size, &locker_mesh, &locker_mat)
+        } else {
+            ("Box", box_size, &box_mesh, &box_mat)
+        };
+
+        commands
+            .spawn((
+                Name::new(format!("{name} {}", container.serial)),
+                SpatialBundle::from_transform(common_transform(common)),
+                Container {
+                    id: container.id,
+                    serial: container.serial,
+                    locker: container.locker,
+                },
+                Dimension(common.dimension),
+                ReplayEntity,
+            ))
+            .with_children(|children| {
+                children.spawn(PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_xyz(0., size.y / 2., 0.),
+                    ..default()
+                });
+
+                // Locks hang on the front, a bit above the middle.
+                let lock_transform = Transform::from_xyz(0., size.y * 0.6, size.z / 2. + 0.04);
+                match container.lock_type {
+                    Some(LockType::Melee) => {
+                        children.spawn((
+                            Name::new("Padlock"),
+                            PbrBundle {
+                                mesh: padlock_mesh.clone(),
+                                material: padlock_mat.clone(),
+                                transform: lock_transform,
+                                ..default()
+                            },
+                        ));
+                    }
+                    Some(LockType::Hack) => {
+                        children.spawn((
+                            Name::new("Hacklock"),
+                            PbrBundle {
+                                mesh: hacklock_mesh.clone(),
+                                material: hacklock_mat.clone(),
+                                transform: lock_transform,
+                                ..default()
+                            },
+                        ));
+                    }
+                    Some(LockType::None) | None => {}
+                }
+
+                let item = container
+                    .consumable_type
+                    .as_ref()
+                    .filter(|&item| *item != IdentifierType::Unknown);
+                if let Some(item) = item {
+                    let category = item.info().and_then(|info| info.category);
+                    let material = item_materials
+                        .entry(category)
+                        .or_insert_with_key(|category| {
+                            let color = item_color(category.as_deref());
+                            materials.add(StandardMaterial {
+                                base_color: color.into(),
+                                emissive: LinearRgba::from(color) * 0.5,
+                                ..default()
+                            })
+                        })
+                        .clone();
+                    children.spawn((
+                        Name::new(item.to_string()),
+                        ContainerItem,
+                        PbrBundle {
+                            mesh: item_mesh.clone(),
+                            material,
+                            transform: Transform::from_xyz(0., size.y / 2., 0.),
+                            ..default()
+                        },
+                    ));
+                }
+            });
+    }
+}
+
+fn spawn_transparency_toggle(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+    transparent: Res<TransparentContainers>,
+) {
+    let Ok(panel) = q_panel.get_single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|children| {
+        children
+            .small_button(toggle_text("Transparent Containers", transparent.0))
+            .insert(TransparencyToggle)
+            .observe(toggle_transparency);
+    });
+}
+
+fn toggle_transparency(_trigger: Trigger<OnPress>, mut transparent: ResMut<TransparentContainers>) {
+    transparent.0 = !transparent.0;
+}
+
+fn apply_transparency(
+    transparent: Res<TransparentContainers>,
+    container_materials: Option<Res<ContainerMaterials>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    q_toggle: Query<&Children, With<TransparencyToggle>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for handle in container_materials.iter().flat_map(|m| &m.0) {
+        if let Some(material) = materials.get_mut(handle) {
+            set_transparent(material, transparent.0);
+        }
+    }
+
+    for children in &q_toggle {
+        set_button_text(
+            children,
+            &mut q_text,
+            toggle_text("Transparent Containers", transparent.0),
+        );
+    }
+}
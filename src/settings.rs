@@ -0,0 +1,44 @@
+//! Panel of viewer settings in the top right corner.
+
+use bevy::{prelude::*, ui::Val::*};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Startup, spawn_settings_panel);
+}
+
+/// Container for the setting toggles. Plugins spawn their buttons as its
+/// children in a startup system ordered after [`spawn_settings_panel`].
+#[derive(Component, Debug)]
+pub struct SettingsPanel;
+
+pub fn spawn_settings_panel(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Settings"),
+        SettingsPanel,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Px(10.0),
+                right: Px(10.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                row_gap: Px(6.0),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+/// Text of a button toggling the setting `name`.
+pub fn toggle_text(name: &str, on: bool) -> String {
+    format!("{name}: {}", if on { "On" } else { "Off" })
+}
+
+/// Replaces the text of the button owning `children`.
+pub fn set_button_text(children: &Children, q_text: &mut Query<&mut Text>, value: String) {
+    let mut texts = q_text.iter_many_mut(children);
+    while let Some(mut text) = texts.fetch_next() {
+        text.sections[0].value.clone_from(&value);
+    }
+}
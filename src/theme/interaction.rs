@@ -1,19 +1,8 @@
 use bevy::prelude::*;
 
-use crate::{asset_tracking::LoadResource, audio::SoundEffect};
-
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<InteractionPalette>();
-    app.load_resource::<InteractionAssets>();
-    app.add_systems(
-        Update,
-        (
-            trigger_on_press,
-            apply_interaction_palette,
-            trigger_interaction_sound_effect,
-        )
-            .run_if(resource_exists::<InteractionAssets>),
-    );
+    app.add_systems(Update, (trigger_on_press, apply_interaction_palette));
 }
 
 /// Palette for widget interactions. Add this to an entity that supports
@@ -58,47 +47,3 @@ fn apply_interaction_palette(
         .into();
     }
 }
-
-#[derive(Resource, Asset, Reflect, Clone)]
-pub struct InteractionAssets {
-    #[dependency]
-    hover: Handle<AudioSource>,
-    #[dependency]
-    press: Handle<AudioSource>,
-}
-
-impl InteractionAssets {
-    pub const PATH_BUTTON_HOVER: &'static str = "audio/sound_effects/button_hover.ogg";
-    pub const PATH_BUTTON_PRESS: &'static str = "audio/sound_effects/button_press.ogg";
-}
-
-impl FromWorld for InteractionAssets {
-    fn from_world(world: &mut World) -> Self {
-        let assets = world.resource::<AssetServer>();
-        Self {
-            hover: assets.load(Self::PATH_BUTTON_HOVER),
-            press: assets.load(Self::PATH_BUTTON_PRESS),
-        }
-    }
-}
-
-fn trigger_interaction_sound_effect(
-    interaction_query: Query<&Interaction, Changed<Interaction>>,
-    interaction_assets: Res<InteractionAssets>,
-    mut commands: Commands,
-) {
-    for interaction in &interaction_query {
-        let source = match interaction {
-            Interaction::Hovered => interaction_assets.hover.clone(),
-            Interaction::Pressed => interaction_assets.press.clone(),
-            _ => continue,
-        };
-        commands.spawn((
-            AudioBundle {
-                source,
-                settings: PlaybackSettings::DESPAWN,
-            },
-            SoundEffect,
-        ));
-    }
-}
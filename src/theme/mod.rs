@@ -0,0 +1,23 @@
+//! Reusable UI widgets & theming.
+
+// Unused utilities may trigger this lints undesirably.
+#![allow(dead_code)]
+
+pub mod interaction;
+pub mod palette;
+mod widgets;
+
+#[allow(unused_imports)]
+pub mod prelude {
+    pub use super::{
+        interaction::{InteractionPalette, OnPress},
+        palette as ui_palette,
+        widgets::{Containers as _, Widgets as _},
+    };
+}
+
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(interaction::plugin);
+}
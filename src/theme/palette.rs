@@ -8,3 +8,20 @@ pub const LABEL_TEXT: Color = Color::srgb(0.867, 0.827, 0.412);
 pub const HEADER_TEXT: Color = Color::srgb(0.867, 0.827, 0.412);
 
 pub const NODE_BACKGROUND: Color = Color::srgb(0.286, 0.478, 0.773);
+
+pub const TIMELINE_BAR_BACKGROUND: Color = Color::srgba(0.1, 0.1, 0.1, 0.8);
+pub const TIMELINE_TRACK: Color = Color::srgb(0.3, 0.3, 0.3);
+pub const TIMELINE_PROGRESS: Color = Color::srgb(0.286, 0.478, 0.773);
+pub const TIMELINE_HANDLE: Color = Color::srgb(0.925, 0.925, 0.925);
+
+pub const MARKER_DOOR_OPENED: Color = Color::srgb(0.392, 0.749, 0.416);
+pub const MARKER_PLAYER_DOWN: Color = Color::srgb(0.863, 0.196, 0.184);
+pub const MARKER_ALARM: Color = Color::srgb(0.976, 0.596, 0.153);
+
+/// Colors of the four player slots, as in game.
+pub const PLAYER_COLORS: [Color; 4] = [
+    Color::srgb(0.761, 0.122, 0.306),
+    Color::srgb(0.094, 0.576, 0.369),
+    Color::srgb(0.125, 0.333, 0.549),
+    Color::srgb(0.478, 0.102, 0.557),
+];
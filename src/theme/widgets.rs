@@ -7,54 +7,28 @@ use crate::theme::{interaction::InteractionPalette, palette::*};
 /// An extension trait for spawning UI widgets.
 pub trait Widgets {
     /// Spawn a simple button with text.
-    fn button(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn button(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
+
+    /// Spawn a compact button with text, sized to fit it.
+    fn small_button(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
 
     /// Spawn a simple header label. Bigger than [`Widgets::label`].
-    fn header(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn header(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
 
     /// Spawn a simple text label.
-    fn label(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn label(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
 }
 
 impl<T: Spawn> Widgets for T {
-    fn button(&mut self, text: impl Into<String>) -> EntityCommands {
-        let mut entity = self.spawn((
-            Name::new("Button"),
-            ButtonBundle {
-                style: Style {
-                    width: Px(200.0),
-                    height: Px(65.0),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                background_color: BackgroundColor(NODE_BACKGROUND),
-                ..default()
-            },
-            InteractionPalette {
-                none: NODE_BACKGROUND,
-                hovered: BUTTON_HOVERED_BACKGROUND,
-                pressed: BUTTON_PRESSED_BACKGROUND,
-            },
-        ));
-        entity.with_children(|children| {
-            children.spawn((
-                Name::new("Button Text"),
-                TextBundle::from_section(
-                    text,
-                    TextStyle {
-                        font_size: 40.0,
-                        color: BUTTON_TEXT,
-                        ..default()
-                    },
-                ),
-            ));
-        });
+    fn button(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
+        spawn_button(self, text, ButtonSize::Normal)
+    }
 
-        entity
+    fn small_button(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
+        spawn_button(self, text, ButtonSize::Small)
     }
 
-    fn header(&mut self, text: impl Into<String>) -> EntityCommands {
+    fn header(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
         let mut entity = self.spawn((
             Name::new("Header"),
             NodeBundle {
@@ -85,7 +59,7 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
-    fn label(&mut self, text: impl Into<String>) -> EntityCommands {
+    fn label(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
         let entity = self.spawn((
             Name::new("Label"),
             TextBundle::from_section(
@@ -105,15 +79,80 @@ impl<T: Spawn> Widgets for T {
     }
 }
 
+#[derive(Clone, Copy)]
+enum ButtonSize {
+    Normal,
+    /// Sized to fit the text.
+    Small,
+}
+
+fn spawn_button(
+    spawner: &mut impl Spawn,
+    text: impl Into<String>,
+    size: ButtonSize,
+) -> EntityCommands<'_> {
+    let (name, style, font_size) = match size {
+        ButtonSize::Normal => (
+            "Button",
+            Style {
+                width: Px(200.0),
+                height: Px(65.0),
+                ..default()
+            },
+            40.0,
+        ),
+        ButtonSize::Small => (
+            "Small Button",
+            Style {
+                padding: UiRect::axes(Px(12.0), Px(4.0)),
+                ..default()
+            },
+            24.0,
+        ),
+    };
+    let mut entity = spawner.spawn((
+        Name::new(name),
+        ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..style
+            },
+            background_color: BackgroundColor(NODE_BACKGROUND),
+            ..default()
+        },
+        InteractionPalette {
+            none: NODE_BACKGROUND,
+            hovered: BUTTON_HOVERED_BACKGROUND,
+            pressed: BUTTON_PRESSED_BACKGROUND,
+        },
+    ));
+    entity.with_children(|children| {
+        children.spawn((
+            Name::new("Button Text"),
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size,
+                    color: BUTTON_TEXT,
+                    ..default()
+                },
+            ),
+        ));
+    });
+
+    entity
+}
+
 /// An extension trait for spawning UI containers.
 pub trait Containers {
     /// Spawns a root node that covers the full screen
     /// and centers its content horizontally and vertically.
-    fn ui_root(&mut self) -> EntityCommands;
+    fn ui_root(&mut self) -> EntityCommands<'_>;
 }
 
 impl Containers for Commands<'_, '_> {
-    fn ui_root(&mut self) -> EntityCommands {
+    fn ui_root(&mut self) -> EntityCommands<'_> {
         self.spawn((
             Name::new("UI Root"),
             NodeBundle {
@@ -138,17 +177,17 @@ impl Containers for Commands<'_, '_> {
 /// are able to spawn entities.
 /// Ideally, this trait should be [part of Bevy itself](https://github.com/bevyengine/bevy/issues/14231).
 trait Spawn {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands;
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_>;
 }
 
 impl Spawn for Commands<'_, '_> {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands {
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_> {
         self.spawn(bundle)
     }
 }
 
 impl Spawn for ChildBuilder<'_> {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands {
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_> {
         self.spawn(bundle)
     }
 }
@@ -0,0 +1,356 @@
+//! Timeline bar along the bottom of the window, with playback controls and
+//! markers for notable events.
+
+use std::{collections::HashSet, time::Duration};
+
+use bevy::{prelude::*, ui::RelativeCursorPosition, ui::Val::*};
+use rrv_core::prelude::{DoorState, DynamicData, EnemyBehaviour, EventData, Snapshot};
+
+use crate::{
+    playback::{ApplySnapshots, PlaybackState},
+    replay::{CurrentReplay, ReplayAsset, ReplayEntity, ReplayLoaded, SpawnReplay},
+    theme::{palette::*, prelude::*},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Startup, spawn_timeline).add_systems(
+        Update,
+        (
+            spawn_markers
+                .in_set(SpawnReplay)
+                .run_if(on_event::<ReplayLoaded>()),
+            scrub_timeline.before(ApplySnapshots),
+            update_timeline
+                .after(ApplySnapshots)
+                .run_if(resource_changed::<PlaybackState>),
+        ),
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    DoorOpened,
+    /// A player's health reached zero.
+    PlayerDown,
+    /// Enemies started fighting while none were. The recorder writes no alarm
+    /// event, this also catches enemies woken up without one. Behaviour events
+    /// are only decoded with the `provisional_enemies` feature.
+    Alarm,
+}
+
+impl MarkerKind {
+    fn color(self) -> Color {
+        match self {
+            Self::DoorOpened => MARKER_DOOR_OPENED,
+            Self::PlayerDown => MARKER_PLAYER_DOWN,
+            Self::Alarm => MARKER_ALARM,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::DoorOpened => "Door Opened",
+            Self::PlayerDown => "Player Down",
+            Self::Alarm => "Alarm",
+        }
+    }
+
+    /// Notable events in `snapshots`, with the timestamps they happened at.
+    fn find(snapshots: &[Snapshot]) -> Vec<(u32, Self)> {
+        let mut markers = vec![];
+        let mut downed = HashSet::new();
+        let mut fighting = HashSet::new();
+        for snapshot in snapshots {
+            for event in &snapshot.events {
+                let timestamp = snapshot.timestamp + u32::from(event.offset);
+                match event.data {
+                    EventData::DoorStatus {
+                        state: DoorState::Open,
+                        ..
+                    } => markers.push((timestamp, Self::DoorOpened)),
+                    EventData::EnemyBehaviour {
+                        id,
+                        behaviour: EnemyBehaviour::Combat,
+                    } => {
+                        if fighting.is_empty() {
+                            markers.push((timestamp, Self::Alarm));
+                        }
+                        fighting.insert(id);
+                    }
+                    EventData::EnemyBehaviour { id, .. } | EventData::Despawn { id, .. } => {
+                        fighting.remove(&id);
+                    }
+                    _ => {}
+                }
+            }
+            for dynamic in &snapshot.dynamics {
+                let DynamicData::PlayerStats(stats) = &dynamic.data else {
+                    continue;
+                };
+                if stats.health as f32 > 0. {
+                    downed.remove(&dynamic.id);
+                } else if downed.insert(dynamic.id) {
+                    markers.push((snapshot.timestamp, Self::PlayerDown));
+                }
+            }
+        }
+        markers
+    }
+}
+
+/// A marker on the timeline, jumps to its time when pressed.
+#[derive(Component, Debug)]
+pub struct TimelineMarker {
+    pub time: Duration,
+}
+
+/// The bar that is dragged to scrub through the replay.
+#[derive(Component, Debug)]
+struct TimelineTrack;
+
+#[derive(Component, Debug)]
+struct TimelineProgress;
+
+#[derive(Component, Debug)]
+struct TimelineHandle;
+
+#[derive(Component, Debug)]
+struct PlayButton;
+
+#[derive(Component, Debug)]
+struct SpeedLabel;
+
+#[derive(Component, Debug)]
+struct TimeLabel;
+
+fn spawn_timeline(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Timeline"),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Px(0.0),
+                    width: Percent(100.0),
+                    padding: UiRect::axes(Px(10.0), Px(8.0)),
+                    column_gap: Px(10.0),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(TIMELINE_BAR_BACKGROUND),
+                ..default()
+            },
+        ))
+        .with_children(|children| {
+            children
+                .small_button("Play")
+                .insert(PlayButton)
+                .observe(toggle_playback);
+            children.small_button("-").observe(slow_down);
+            children.spawn((
+                Name::new("Speed"),
+                SpeedLabel,
+                TextBundle::from_section("1x", label_style()),
+            ));
+            children.small_button("+").observe(speed_up);
+            children.spawn((
+                Name::new("Time"),
+                TimeLabel,
+                TextBundle::from_section("00:00 / 00:00", label_style()),
+            ));
+            children
+                .spawn((
+                    Name::new("Track"),
+                    TimelineTrack,
+                    NodeBundle {
+                        style: Style {
+                            flex_grow: 1.0,
+                            height: Px(12.0),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(TIMELINE_TRACK),
+                        ..default()
+                    },
+                    Interaction::default(),
+                    RelativeCursorPosition::default(),
+                ))
+                .with_children(|children| {
+                    children.spawn((
+                        Name::new("Progress"),
+                        TimelineProgress,
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                width: Percent(0.0),
+                                height: Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BackgroundColor(TIMELINE_PROGRESS),
+                            ..default()
+                        },
+                    ));
+                    children.spawn((
+                        Name::new("Handle"),
+                        TimelineHandle,
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Percent(0.0),
+                                top: Px(-4.0),
+                                width: Px(4.0),
+                                height: Px(20.0),
+                                margin: UiRect::left(Px(-2.0)),
+                                ..default()
+                            },
+                            background_color: BackgroundColor(TIMELINE_HANDLE),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn label_style() -> TextStyle {
+    TextStyle {
+        font_size: 24.0,
+        color: LABEL_TEXT,
+        ..default()
+    }
+}
+
+fn spawn_markers(
+    mut commands: Commands,
+    current: Res<CurrentReplay>,
+    replays: Res<Assets<ReplayAsset>>,
+    q_track: Query<Entity, With<TimelineTrack>>,
+) {
+    let (Some(replay), Ok(track)) = (replays.get(&current.0), q_track.get_single()) else {
+        return;
+    };
+    let Some(duration) = replay.snapshots.last().map(|s| s.timestamp as f32) else {
+        return;
+    };
+
+    commands.entity(track).with_children(|children| {
+        for (timestamp, kind) in MarkerKind::find(&replay.snapshots) {
+            let left = if duration > 0. {
+                (timestamp as f32 / duration * 100.).min(100.)
+            } else {
+                0.
+            };
+            children
+                .spawn((
+                    Name::new(kind.name()),
+                    TimelineMarker {
+                        time: Duration::from_millis(timestamp.into()),
+                    },
+                    ReplayEntity,
+                    ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Percent(left),
+                            bottom: Px(14.0),
+                            width: Px(4.0),
+                            height: Px(12.0),
+                            margin: UiRect::left(Px(-2.0)),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(kind.color()),
+                        ..default()
+                    },
+                    InteractionPalette {
+                        none: kind.color(),
+                        hovered: TIMELINE_HANDLE,
+                        pressed: TIMELINE_HANDLE,
+                    },
+                ))
+                .observe(seek_to_marker);
+        }
+    });
+}
+
+fn toggle_playback(_trigger: Trigger<OnPress>, mut playback: ResMut<PlaybackState>) {
+    playback.toggle();
+}
+
+fn slow_down(_trigger: Trigger<OnPress>, mut playback: ResMut<PlaybackState>) {
+    let speed = playback.speed / 2.;
+    playback.set_speed(speed);
+}
+
+fn speed_up(_trigger: Trigger<OnPress>, mut playback: ResMut<PlaybackState>) {
+    let speed = playback.speed * 2.;
+    playback.set_speed(speed);
+}
+
+fn seek_to_marker(
+    trigger: Trigger<OnPress>,
+    q_markers: Query<&TimelineMarker>,
+    mut playback: ResMut<PlaybackState>,
+) {
+    if let Ok(marker) = q_markers.get(trigger.entity()) {
+        playback.seek(marker.time);
+    }
+}
+
+fn scrub_timeline(
+    q_track: Query<(&Interaction, &RelativeCursorPosition), With<TimelineTrack>>,
+    mut playback: ResMut<PlaybackState>,
+) {
+    let Ok((Interaction::Pressed, cursor)) = q_track.get_single() else {
+        return;
+    };
+    let Some(position) = cursor.normalized else {
+        return;
+    };
+
+    let time = playback.duration.mul_f32(position.x.clamp(0., 1.));
+    if time != playback.time {
+        playback.seek(time);
+    }
+}
+
+fn update_timeline(
+    playback: Res<PlaybackState>,
+    mut q_progress: Query<&mut Style, (With<TimelineProgress>, Without<TimelineHandle>)>,
+    mut q_handle: Query<&mut Style, With<TimelineHandle>>,
+    q_play_button: Query<&Children, With<PlayButton>>,
+    mut q_speed_label: Query<&mut Text, (With<SpeedLabel>, Without<TimeLabel>)>,
+    mut q_time_label: Query<&mut Text, (With<TimeLabel>, Without<SpeedLabel>)>,
+    mut q_text: Query<&mut Text, (Without<SpeedLabel>, Without<TimeLabel>)>,
+) {
+    let progress = if playback.duration.is_zero() {
+        0.
+    } else {
+        playback.time.as_secs_f32() / playback.duration.as_secs_f32() * 100.
+    };
+    for mut style in &mut q_progress {
+        style.width = Percent(progress);
+    }
+    for mut style in &mut q_handle {
+        style.left = Percent(progress);
+    }
+
+    for children in &q_play_button {
+        let mut texts = q_text.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = if playback.paused { "Play" } else { "Pause" }.into();
+        }
+    }
+    for mut text in &mut q_speed_label {
+        text.sections[0].value = format!("{}x", playback.speed);
+    }
+    for mut text in &mut q_time_label {
+        text.sections[0].value = format!(
+            "{} / {}",
+            format_time(playback.time),
+            format_time(playback.duration)
+        );
+    }
+}
+
+/// Formats `time` as minutes and seconds, like `12:05`.
+fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}